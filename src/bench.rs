@@ -0,0 +1,267 @@
+use std::{collections::BTreeMap, fmt::Display, path::Path, time::Duration};
+
+use crate::{
+    error::AocError,
+    input::{hash, normalize},
+    solution::{Part, Solution},
+    store,
+};
+
+/// A step of solving a puzzle which is timed separately.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Phase {
+    Parse,
+    Part(Part),
+    /// Parsing, and then solving both parts.
+    Total,
+}
+impl Phase {
+    pub const ALL: [Phase; 4] = [
+        Phase::Parse,
+        Phase::Part(Part::One),
+        Phase::Part(Part::Two),
+        Phase::Total,
+    ];
+
+    /// The key used for this phase in a baseline.
+    pub fn key(self) -> &'static str {
+        match self {
+            Phase::Parse => "parse",
+            Phase::Part(Part::One) => "part1",
+            Phase::Part(Part::Two) => "part2",
+            Phase::Total => "total",
+        }
+    }
+}
+impl Display for Phase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Phase::Parse => f.write_str("Parse"),
+            Phase::Part(p) => write!(f, "Part {}", p.number()),
+            Phase::Total => f.write_str("Total"),
+        }
+    }
+}
+
+/// The distribution of a number of timed runs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stats {
+    pub min: Duration,
+    pub median: Duration,
+    pub p95: Duration,
+}
+impl Stats {
+    /// Summarises the samples, of which there must be at least one.
+    pub fn new(mut samples: Vec<Duration>) -> Self {
+        assert!(!samples.is_empty(), "No samples to summarise");
+        samples.sort_unstable();
+        let nth = |fraction: f64| {
+            let index = (fraction * samples.len() as f64).ceil() as usize;
+            samples[index.clamp(1, samples.len()) - 1]
+        };
+        Self {
+            min: samples[0],
+            median: nth(0.5),
+            p95: nth(0.95),
+        }
+    }
+}
+
+/// How long each phase of one day took over a number of runs.
+pub struct Measurement {
+    pub day: u32,
+    pub hash: u64,
+    /// The size of the input, in bytes.
+    pub bytes: usize,
+    pub runs: u32,
+    /// Every phase, in the order of [`Phase::ALL`].
+    pub phases: Vec<(Phase, Stats)>,
+}
+impl Measurement {
+    pub fn get(&self, phase: Phase) -> Option<Stats> {
+        self.phases
+            .iter()
+            .find(|(p, _)| *p == phase)
+            .map(|&(_, stats)| stats)
+    }
+
+    /// Input bytes processed per second, from the median total time.
+    pub fn throughput(&self) -> f64 {
+        let median = self.get(Phase::Total).map_or(Duration::ZERO, |s| s.median);
+        self.bytes as f64 / median.as_secs_f64()
+    }
+}
+
+/// Solves both parts of a day `warmup` times without measuring, and then
+/// `runs` times, measuring each phase.
+pub fn measure(
+    solution: &dyn Solution,
+    input: &str,
+    warmup: u32,
+    runs: u32,
+) -> Result<Measurement, AocError> {
+    let solve = || -> Result<_, AocError> {
+        let timings = solution.timed(input, &Part::BOTH)?;
+        for p in &timings.parts {
+            p.answer.clone()?;
+        }
+        Ok(timings)
+    };
+    for _ in 0..warmup {
+        solve()?;
+    }
+    let mut samples = BTreeMap::<Phase, Vec<Duration>>::new();
+    for _ in 0..runs.max(1) {
+        let timings = solve()?;
+        samples.entry(Phase::Parse).or_default().push(timings.parse);
+        for p in &timings.parts {
+            samples
+                .entry(Phase::Part(p.part))
+                .or_default()
+                .push(p.elapsed);
+        }
+        samples
+            .entry(Phase::Total)
+            .or_default()
+            .push(timings.total());
+    }
+    Ok(Measurement {
+        day: solution.day(),
+        hash: hash(&normalize(input)),
+        bytes: input.len(),
+        runs: runs.max(1),
+        phases: samples
+            .into_iter()
+            .map(|(phase, samples)| (phase, Stats::new(samples)))
+            .collect(),
+    })
+}
+
+/// Median times from an earlier benchmark, keyed by day and input hash.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Baseline(BTreeMap<(u32, u64), BTreeMap<Phase, Duration>>);
+
+impl Baseline {
+    pub fn get(&self, day: u32, hash: u64, phase: Phase) -> Option<Duration> {
+        self.0.get(&(day, hash))?.get(&phase).copied()
+    }
+
+    /// Records the median of every phase, replacing any previously recorded for the same input.
+    pub fn insert(&mut self, m: &Measurement) {
+        let entry = self.0.entry((m.day, m.hash)).or_default();
+        for &(phase, stats) in &m.phases {
+            entry.insert(phase, stats.median);
+        }
+    }
+
+    /// Reads a baseline from a file. A missing file has no times.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        store::read(path)?
+            .parse()
+            .map_err(|e| format!("couldn't parse {}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        store::save(path, &self.to_string())
+    }
+}
+
+impl std::str::FromStr for Baseline {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut output = Self::default();
+        for (key, entries) in store::parse(s)? {
+            let times = output.0.entry(key).or_default();
+            for (name, nanos) in entries {
+                let phase = Phase::ALL
+                    .iter()
+                    .copied()
+                    .find(|p| p.key() == name)
+                    .ok_or_else(|| format!("unknown phase '{}' for day {}", name, key.0))?;
+                times.insert(phase, Duration::from_nanos(nanos));
+            }
+        }
+        Ok(output)
+    }
+}
+
+impl Display for Baseline {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sections = self.0.iter().map(|(&key, times)| {
+            let entries = times
+                .iter()
+                .map(|(phase, d)| (phase.key(), d.as_nanos() as u64))
+                .collect();
+            (key, entries)
+        });
+        f.write_str(&store::write(
+            &[
+                "Median benchmark times in nanoseconds, keyed by day and input hash.",
+                "Written by `bench --save`, and compared by `bench --baseline`.",
+            ],
+            sections,
+        ))
+    }
+}
+
+/// The relative change from `baseline` to `current`, such as `+12.5%`.
+pub fn format_change(baseline: Duration, current: Duration) -> String {
+    let change = current.as_secs_f64() / baseline.as_secs_f64() - 1.0;
+    format!("{:+.1}%", change * 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day06::Day06;
+
+    fn micros(values: &[u64]) -> Vec<Duration> {
+        values.iter().map(|&v| Duration::from_micros(v)).collect()
+    }
+
+    #[test]
+    fn summarises_samples() {
+        let stats = Stats::new(micros(&[5, 1, 4, 2, 3]));
+        assert_eq!(Duration::from_micros(1), stats.min);
+        assert_eq!(Duration::from_micros(3), stats.median);
+        assert_eq!(Duration::from_micros(5), stats.p95);
+
+        let stats = Stats::new((1..=100).map(Duration::from_micros).collect());
+        assert_eq!(Duration::from_micros(50), stats.median);
+        assert_eq!(Duration::from_micros(95), stats.p95);
+
+        let stats = Stats::new(micros(&[7]));
+        assert_eq!(stats.min, stats.p95);
+    }
+
+    #[test]
+    fn measures_every_phase() {
+        let m = measure(&Day06, "3,4,3,1,2", 1, 3).unwrap();
+        assert_eq!(3, m.runs);
+        assert_eq!(9, m.bytes);
+        assert_eq!(
+            Phase::ALL.to_vec(),
+            m.phases.iter().map(|(p, _)| *p).collect::<Vec<_>>()
+        );
+        assert!(m.throughput() > 0.0);
+        assert!(measure(&Day06, "3,x", 0, 1).is_err());
+    }
+
+    #[test]
+    fn round_trips_baseline() {
+        let mut baseline = Baseline::default();
+        baseline.insert(&measure(&Day06, "3,4,3,1,2\n", 0, 1).unwrap());
+        let parsed = baseline.to_string().parse::<Baseline>().unwrap();
+        assert_eq!(baseline, parsed);
+        assert!(parsed.get(6, hash("3,4,3,1,2\n"), Phase::Total).is_some());
+        assert!("[day06.ff]\npart3 = 1\n".parse::<Baseline>().is_err());
+    }
+
+    #[test]
+    fn formats_changes() {
+        let ms = Duration::from_millis;
+        assert_eq!("+50.0%", format_change(ms(2), ms(3)));
+        assert_eq!("-25.0%", format_change(ms(4), ms(3)));
+    }
+}
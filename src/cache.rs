@@ -0,0 +1,180 @@
+//! Answers from earlier runs, so that unchanged inputs needn't be solved again.
+//!
+//! Answers are keyed by day, input hash and part, and by the day's solver
+//! [version](crate::Puzzle::VERSION), so that a changed solver never reuses
+//! the answers of the old one. Stored as a small subset of TOML:
+//!
+//! ```toml
+//! [day05.0123456789abcdef]
+//! version = 1
+//! part1 = 4655
+//! part2 = 20500
+//! ```
+
+use std::{collections::BTreeMap, convert::TryFrom, fmt::Display, path::Path};
+
+use crate::{
+    solution::{Answer, Part},
+    store,
+};
+
+/// The file, relative to the working directory, where answers are cached.
+pub const DEFAULT_PATH: &str = ".aoc-cache.toml";
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Cache(BTreeMap<(u32, u64), Entry>);
+
+/// The answers for one input, from one version of the solver.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Entry {
+    version: u32,
+    answers: [Option<Answer>; 2],
+}
+
+impl Cache {
+    /// The cached answer, if there is one from the same version of the solver.
+    pub fn get(&self, day: u32, hash: u64, version: u32, part: Part) -> Option<Answer> {
+        let entry = self.0.get(&(day, hash))?;
+        match entry.version == version {
+            true => entry.answers[part.number() as usize - 1],
+            false => None,
+        }
+    }
+
+    /// Caches an answer, discarding any from other versions of the solver.
+    pub fn insert(&mut self, day: u32, hash: u64, version: u32, part: Part, answer: Answer) {
+        let entry = self.0.entry((day, hash)).or_default();
+        if entry.version != version {
+            *entry = Entry {
+                version,
+                answers: [None; 2],
+            };
+        }
+        entry.answers[part.number() as usize - 1] = Some(answer);
+    }
+
+    /// How many answers are cached.
+    pub fn len(&self) -> usize {
+        self.0
+            .values()
+            .flat_map(|e| e.answers.iter())
+            .filter(|a| a.is_some())
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Reads the cache from a file. A missing file is an empty cache.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        store::read(path)?
+            .parse()
+            .map_err(|e| format!("couldn't parse {}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        store::save(path, &self.to_string())
+    }
+
+    /// Removes the cache file, returning how many answers it held.
+    pub fn clear(path: &Path) -> Result<usize, String> {
+        let count = Self::load(path)?.len();
+        match std::fs::remove_file(path) {
+            Ok(()) => Ok(count),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+            Err(e) => Err(format!("couldn't remove {}: {}", path.display(), e)),
+        }
+    }
+}
+
+impl std::str::FromStr for Cache {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut output = Self::default();
+        for ((day, hash), entries) in store::parse(s)? {
+            let mut entry = Entry::default();
+            for (key, value) in entries {
+                match key {
+                    "version" => {
+                        entry.version = u32::try_from(value).map_err(|_| {
+                            format!("version {} for day {} is too large", value, day)
+                        })?
+                    }
+                    "part1" => entry.answers[0] = Some(value),
+                    "part2" => entry.answers[1] = Some(value),
+                    other => return Err(format!("unknown key '{}' for day {}", other, day)),
+                }
+            }
+            output.0.insert((day, hash), entry);
+        }
+        Ok(output)
+    }
+}
+
+impl Display for Cache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sections = self.0.iter().map(|(&key, entry)| {
+            let mut entries = vec![("version", u64::from(entry.version))];
+            entries.extend(
+                ["part1", "part2"]
+                    .iter()
+                    .zip(&entry.answers)
+                    .filter_map(|(&name, answer)| Some((name, (*answer)?))),
+            );
+            (key, entries)
+        });
+        f.write_str(&store::write(
+            &[
+                "Answers cached by `run`, keyed by day and input hash.",
+                "Safe to delete, or remove with `cache clear`.",
+            ],
+            sections,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_by_version() {
+        let mut cache = Cache::default();
+        cache.insert(5, 0xff, 1, Part::One, 4655);
+        cache.insert(5, 0xff, 1, Part::Two, 20500);
+        assert_eq!(Some(4655), cache.get(5, 0xff, 1, Part::One));
+        assert_eq!(None, cache.get(5, 0xff, 2, Part::One));
+        assert_eq!(None, cache.get(5, 0xfe, 1, Part::One));
+        assert_eq!(None, cache.get(6, 0xff, 1, Part::One));
+
+        cache.insert(5, 0xff, 2, Part::One, 4655);
+        assert_eq!(None, cache.get(5, 0xff, 1, Part::Two));
+        assert_eq!(1, cache.len());
+    }
+
+    #[test]
+    fn round_trips() {
+        let mut cache = Cache::default();
+        cache.insert(5, 0xff, 3, Part::Two, 20500);
+        cache.insert(1, 0, 1, Part::One, 7);
+        let text = cache.to_string();
+        assert!(text.contains("[day05.00000000000000ff]\nversion = 3\npart2 = 20500\n"));
+        assert_eq!(Ok(cache), text.parse());
+        assert!("[day01.0]\npart3 = 1\n".parse::<Cache>().is_err());
+    }
+
+    #[test]
+    fn clears_file() {
+        let path = std::env::temp_dir().join(format!("aoc-cache-{}.toml", std::process::id()));
+        let mut cache = Cache::default();
+        cache.insert(1, 0, 1, Part::One, 7);
+        cache.insert(1, 0, 1, Part::Two, 5);
+        cache.save(&path).unwrap();
+        assert_eq!(cache, Cache::load(&path).unwrap());
+        assert_eq!(Ok(2), Cache::clear(&path));
+        assert!(!path.exists());
+        assert_eq!(Ok(0), Cache::clear(&path));
+    }
+}
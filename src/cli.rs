@@ -0,0 +1,892 @@
+use std::{fmt::Display, path::PathBuf, str::FromStr};
+
+use rust_aoc_2021::{
+    day02::{model, Surface},
+    input::Provider,
+    Part,
+};
+
+use crate::output::Format;
+
+pub const USAGE: &str = r#"Usage: rust-aoc-2021 <command> [options]
+
+Commands:
+  run         Solve one day, or every day
+  verify      Check every day's answers against those recorded
+  bench       Time every day over many runs
+  generate    Write a random puzzle input
+  crosscheck  Check the solvers against slow reference solutions
+  serve       Answer puzzle inputs sent over HTTP
+  report      Analyse the depths of a day 1 input
+  dive        Follow a day 2 input with any model of the submarine
+  cache clear Forget the answers cached by run
+  list        List the days which have solutions
+  help        Print this message
+
+Options for run:
+  -d, --day <N>          The day to solve
+  -a, --all              Solve every day
+  -p, --part <1|2>       Only solve one part
+  -i, --input <PATH>     Read the puzzle input from a file, or stdin if PATH is -
+      --input-dir <DIR>  Read inputs named dayNN.txt from DIR [default: inputs]
+      --inputs <DIR>     Solve one day for every file in DIR, such as inputs
+                         from several people
+  -f, --format <FORMAT>  Print results as text, json or csv [default: text]
+  -e, --example [NAME]   Solve the puzzle examples, or only the one named NAME,
+                         and check their answers
+  -t, --trace            Print each solver's intermediate steps to stderr
+  -m, --memory           Count the allocations, bytes allocated and peak
+                         memory of each phase
+  -j, --jobs <N>         Solve this many inputs at once [default: one per CPU]
+      --no-cache         Solve every input, rather than reusing the answers
+                         of earlier runs for unchanged inputs
+
+Options for verify:
+  -d, --day <N>          Only check one day
+      --answers <PATH>   The recorded answers [default: answers.toml]
+      --record           Record answers for inputs which have none
+  -j, --jobs <N>         As for run
+  -i, --input <PATH>, --input-dir <DIR>
+                         As for run
+
+Options for bench:
+  -d, --day <N>          Only time one day
+      --warmup <N>       Runs to discard before timing [default: 3]
+      --runs <N>         Runs to time [default: 20]
+      --save <PATH>      Save the median times as a baseline
+      --baseline <PATH>  Compare the median times with a saved baseline
+  -j, --jobs <N>         Time this many days at once, which may slow each
+                         of them down [default: 1]
+  -i, --input <PATH>, --input-dir <DIR>
+                         As for run
+
+Options for crosscheck:
+  -d, --day <N>          Only check one day
+      --cases <N>        Generated inputs to check for each day [default: 100]
+  -s, --size <N>         The largest generated input [default: 50]
+      --seed <N>         Check the same generated inputs as an earlier run
+                         [default: a new seed, which is printed]
+  -i, --input <PATH>, --input-dir <DIR>
+                         As for run
+
+Options for serve:
+      --port <N>         The port to listen on, on localhost [default: 2021]
+
+Options for report:
+  -w, --window <N>       Depths in each moving average [default: 3]
+      --width <N>        Columns in the depth chart [default: 60]
+      --height <N>       Rows in the depth chart [default: 12]
+  -i, --input <PATH>, --input-dir <DIR>
+                         As for run
+
+Options for dive:
+  -M, --model <NAME>     How to interpret the instructions: direct, as in
+                         part 1, or aimed, as in part 2 [default: aimed]
+      --surface <POLICY> What to do above the surface: error, clamp or
+                         allow-negative [default: error]
+  -i, --input <PATH>, --input-dir <DIR>
+                         As for run
+
+Options for generate:
+  -d, --day <N>          The day to write an input for
+  -s, --size <N>         How many lines, boards, fish and so on to write
+                         [default: as many as the puzzle inputs]
+      --seed <N>         Generate the same input as an earlier run
+                         [default: a new seed, which is printed]
+
+Inputs which are not found fall back to those embedded in the binary."#;
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Run(Run),
+    Verify(Verify),
+    Bench(Bench),
+    Generate(Generate),
+    Crosscheck(Crosscheck),
+    Serve(Serve),
+    Report(Report),
+    Dive(Dive),
+    /// Remove the cached answers.
+    ClearCache,
+    List,
+    Help,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Run {
+    pub days: Selection,
+    pub part: Option<Part>,
+    pub input: Provider,
+    pub format: Format,
+    /// Solve these examples instead of the puzzle input.
+    pub examples: Option<Examples>,
+    /// Print each solver's intermediate state to stderr.
+    pub trace: bool,
+    /// How many days to solve at once, or `None` for one per CPU.
+    pub jobs: Option<usize>,
+    /// Solve the day for every file in this directory, instead of for one input.
+    pub batch: Option<PathBuf>,
+    /// Reuse answers cached by earlier runs.
+    pub cache: bool,
+    /// Count the memory allocated by each phase.
+    pub memory: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Examples {
+    All,
+    Named(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Verify {
+    pub days: Selection,
+    pub input: Provider,
+    pub answers: PathBuf,
+    pub record: bool,
+    /// How many days to solve at once, or `None` for one per CPU.
+    pub jobs: Option<usize>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Bench {
+    pub days: Selection,
+    pub input: Provider,
+    pub warmup: u32,
+    pub runs: u32,
+    pub save: Option<PathBuf>,
+    pub baseline: Option<PathBuf>,
+    /// How many days to time at once.
+    pub jobs: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Generate {
+    pub day: u32,
+    pub size: Option<usize>,
+    pub seed: Option<u64>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Crosscheck {
+    pub days: Selection,
+    pub input: Provider,
+    /// How many generated inputs to check for each day.
+    pub cases: u32,
+    /// The largest size of generated input.
+    pub size: usize,
+    pub seed: Option<u64>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Serve {
+    pub port: u16,
+}
+
+/// Options for analysing a day 1 input.
+#[derive(Debug, PartialEq)]
+pub struct Report {
+    pub input: Provider,
+    pub window: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+/// Options for following a day 2 input.
+#[derive(Debug, PartialEq)]
+pub struct Dive {
+    pub input: Provider,
+    /// The name of a [model](rust_aoc_2021::day02::model::MODELS).
+    pub model: String,
+    pub surface: Surface,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Selection {
+    Day(u32),
+    All,
+}
+
+/// The command line could not be understood.
+#[derive(Debug, PartialEq)]
+pub struct UsageError(String);
+impl Display for UsageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Command, UsageError> {
+    let mut args = args.into_iter();
+    match args.next().as_deref() {
+        Some("run") => parse_run(Options::new(args)?),
+        Some("verify") => parse_verify(Options::new(args)?),
+        Some("bench") => parse_bench(Options::new(args)?),
+        Some("generate") => parse_generate(Options::new(args)?),
+        Some("crosscheck") => parse_crosscheck(Options::new(args)?),
+        Some("serve") => parse_serve(Options::new(args)?),
+        Some("report") => parse_report(Options::new(args)?),
+        Some("dive") => parse_dive(Options::new(args)?),
+        Some("cache") => match args.next().as_deref() {
+            Some("clear") => Options::new(args)?.finish().map(|_| Command::ClearCache),
+            Some(other) => Err(UsageError(format!("unknown cache command '{}'", other))),
+            None => Err(UsageError("expected `cache clear`".to_string())),
+        },
+        Some("list") => Options::new(args)?.finish().map(|_| Command::List),
+        Some("help") | Some("-h") | Some("--help") => Ok(Command::Help),
+        Some(other) => Err(UsageError(format!("unknown command '{}'", other))),
+        None => Err(UsageError("no command given".to_string())),
+    }
+}
+
+fn parse_run(mut options: Options) -> Result<Command, UsageError> {
+    let day = options.take(&["-d", "--day"])?;
+    let all = options.take_flag(&["-a", "--all"]);
+    let part = options.take(&["-p", "--part"])?;
+    let format = options.take(&["-f", "--format"])?;
+    let trace = options.take_flag(&["-t", "--trace"]);
+    let jobs = options.take_jobs()?;
+    let batch = options.take(&["--inputs"])?.map(PathBuf::from);
+    let cache = !options.take_flag(&["--no-cache"]);
+    let memory = options.take_flag(&["-m", "--memory"]);
+    let examples = match options.take_optional(&["-e", "--example"]) {
+        None => None,
+        Some(None) => Some(Examples::All),
+        Some(Some(name)) => Some(Examples::Named(name)),
+    };
+
+    let days = match (day, all) {
+        (Some(day), false) => Selection::Day(parse_number("--day", &day)?),
+        (None, true) => Selection::All,
+        (Some(_), true) => return Err(UsageError("--day and --all are exclusive".to_string())),
+        (None, false) => return Err(UsageError("one of --day or --all is required".to_string())),
+    };
+    let input = options.take_provider(&days)?;
+    options.finish()?;
+    if examples.is_some() && input != Provider::default() {
+        return Err(UsageError(
+            "--example can't be used with --input or --input-dir".to_string(),
+        ));
+    }
+    if batch.is_some() {
+        if days == Selection::All {
+            return Err(UsageError(
+                "--inputs can only be used with a single --day".to_string(),
+            ));
+        }
+        if examples.is_some() || input != Provider::default() {
+            return Err(UsageError(
+                "--inputs can't be used with --example, --input or --input-dir".to_string(),
+            ));
+        }
+    }
+    let part = match part.as_deref() {
+        None => None,
+        Some("1") => Some(Part::One),
+        Some("2") => Some(Part::Two),
+        Some(other) => {
+            return Err(UsageError(format!(
+                "--part must be 1 or 2, but was '{}'",
+                other
+            )))
+        }
+    };
+    let format = match format.as_deref() {
+        None | Some("text") => Format::Text,
+        Some("json") => Format::Json,
+        Some("csv") => Format::Csv,
+        Some(other) => {
+            return Err(UsageError(format!(
+                "--format must be text, json or csv, but was '{}'",
+                other
+            )))
+        }
+    };
+    Ok(Command::Run(Run {
+        days,
+        part,
+        input,
+        format,
+        examples,
+        trace,
+        jobs,
+        batch,
+        cache,
+        memory,
+    }))
+}
+
+fn parse_verify(mut options: Options) -> Result<Command, UsageError> {
+    let days = match options.take(&["-d", "--day"])? {
+        Some(day) => Selection::Day(parse_number("--day", &day)?),
+        None => Selection::All,
+    };
+    let input = options.take_provider(&days)?;
+    let answers = options
+        .take(&["--answers"])?
+        .unwrap_or_else(|| rust_aoc_2021::verify::DEFAULT_PATH.to_string());
+    let record = options.take_flag(&["--record"]);
+    let jobs = options.take_jobs()?;
+    options.finish()?;
+    Ok(Command::Verify(Verify {
+        days,
+        input,
+        answers: PathBuf::from(answers),
+        record,
+        jobs,
+    }))
+}
+
+fn parse_bench(mut options: Options) -> Result<Command, UsageError> {
+    let days = match options.take(&["-d", "--day"])? {
+        Some(day) => Selection::Day(parse_number("--day", &day)?),
+        None => Selection::All,
+    };
+    let input = options.take_provider(&days)?;
+    let warmup = match options.take(&["--warmup"])? {
+        Some(n) => parse_number("--warmup", &n)?,
+        None => 3,
+    };
+    let runs = match options.take(&["--runs"])? {
+        Some(n) => parse_number("--runs", &n)?,
+        None => 20,
+    };
+    if runs == 0 {
+        return Err(UsageError("--runs must be at least 1".to_string()));
+    }
+    let save = options.take(&["--save"])?.map(PathBuf::from);
+    let baseline = options.take(&["--baseline"])?.map(PathBuf::from);
+    let jobs = options.take_jobs()?.unwrap_or(1);
+    options.finish()?;
+    Ok(Command::Bench(Bench {
+        days,
+        input,
+        warmup,
+        runs,
+        save,
+        baseline,
+        jobs,
+    }))
+}
+
+fn parse_generate(mut options: Options) -> Result<Command, UsageError> {
+    let day = match options.take(&["-d", "--day"])? {
+        Some(day) => parse_number("--day", &day)?,
+        None => return Err(UsageError("--day is required".to_string())),
+    };
+    let size = match options.take(&["-s", "--size"])? {
+        Some(n) => Some(parse_number("--size", &n)?),
+        None => None,
+    };
+    if size == Some(0) {
+        return Err(UsageError("--size must be at least 1".to_string()));
+    }
+    let seed = match options.take(&["--seed"])? {
+        Some(n) => Some(parse_number("--seed", &n)?),
+        None => None,
+    };
+    options.finish()?;
+    Ok(Command::Generate(Generate { day, size, seed }))
+}
+
+fn parse_crosscheck(mut options: Options) -> Result<Command, UsageError> {
+    let days = match options.take(&["-d", "--day"])? {
+        Some(day) => Selection::Day(parse_number("--day", &day)?),
+        None => Selection::All,
+    };
+    let input = options.take_provider(&days)?;
+    let cases = match options.take(&["--cases"])? {
+        Some(n) => parse_number("--cases", &n)?,
+        None => 100,
+    };
+    let size = match options.take(&["-s", "--size"])? {
+        Some(n) => parse_number("--size", &n)?,
+        None => 50,
+    };
+    if size == 0 {
+        return Err(UsageError("--size must be at least 1".to_string()));
+    }
+    let seed = match options.take(&["--seed"])? {
+        Some(n) => Some(parse_number("--seed", &n)?),
+        None => None,
+    };
+    options.finish()?;
+    Ok(Command::Crosscheck(Crosscheck {
+        days,
+        input,
+        cases,
+        size,
+        seed,
+    }))
+}
+
+fn parse_serve(mut options: Options) -> Result<Command, UsageError> {
+    let port = match options.take(&["--port"])? {
+        Some(port) => parse_number("--port", &port)?,
+        None => 2021,
+    };
+    options.finish()?;
+    Ok(Command::Serve(Serve { port }))
+}
+
+fn parse_report(mut options: Options) -> Result<Command, UsageError> {
+    let input = options.take_provider(&Selection::Day(1))?;
+    let window = options.take_count(&["-w", "--window"], 3)?;
+    let width = options.take_count(&["--width"], 60)?;
+    let height = options.take_count(&["--height"], 12)?;
+    options.finish()?;
+    Ok(Command::Report(Report {
+        input,
+        window,
+        width,
+        height,
+    }))
+}
+
+fn parse_dive(mut options: Options) -> Result<Command, UsageError> {
+    let input = options.take_provider(&Selection::Day(2))?;
+    let model = options
+        .take(&["-M", "--model"])?
+        .unwrap_or_else(|| "aimed".to_string());
+    if model::find(&model).is_none() {
+        let names = model::MODELS.iter().map(|m| m.name).collect::<Vec<_>>();
+        return Err(UsageError(format!(
+            "--model must be one of {}, but was '{}'",
+            names.join(", "),
+            model
+        )));
+    }
+    let surface = match options.take(&["--surface"])? {
+        Some(s) => s.parse().map_err(UsageError)?,
+        None => Surface::default(),
+    };
+    options.finish()?;
+    Ok(Command::Dive(Dive {
+        input,
+        model,
+        surface,
+    }))
+}
+
+fn parse_number<T: FromStr>(name: &str, value: &str) -> Result<T, UsageError> {
+    value
+        .parse()
+        .map_err(|_| UsageError(format!("{} must be a number, but was '{}'", name, value)))
+}
+
+/// The options following a command, as `--name value`, `--name=value` or `--flag`.
+struct Options(Vec<(String, Option<String>)>);
+impl Options {
+    fn new(args: impl Iterator<Item = String>) -> Result<Self, UsageError> {
+        let mut output = Vec::new();
+        let mut args = args.peekable();
+        while let Some(arg) = args.next() {
+            if !arg.starts_with('-') {
+                return Err(UsageError(format!("unexpected argument '{}'", arg)));
+            }
+            if let Some((name, value)) = arg.split_once('=') {
+                output.push((name.to_string(), Some(value.to_string())));
+            } else {
+                let value = args.next_if(|next| !next.starts_with('-') || next == "-");
+                output.push((arg, value));
+            }
+        }
+        Ok(Self(output))
+    }
+
+    /// Removes an option which requires a value.
+    fn take(&mut self, names: &[&str]) -> Result<Option<String>, UsageError> {
+        match self.remove(names) {
+            Some((_, Some(value))) => Ok(Some(value)),
+            Some((name, None)) => Err(UsageError(format!("{} requires a value", name))),
+            None => Ok(None),
+        }
+    }
+
+    /// Removes an option whose value may be left out.
+    fn take_optional(&mut self, names: &[&str]) -> Option<Option<String>> {
+        self.remove(names).map(|(_, value)| value)
+    }
+
+    /// Removes an option which must not have a value.
+    fn take_flag(&mut self, names: &[&str]) -> bool {
+        match self.remove(names) {
+            Some((name, Some(value))) => {
+                // the "value" was really a stray argument; put it back so it is reported.
+                self.0.push((value, None));
+                self.0.push((name, None));
+                self.remove(names).is_some()
+            }
+            Some((_, None)) => true,
+            None => false,
+        }
+    }
+
+    /// Removes the number of worker threads, which must be at least 1.
+    fn take_jobs(&mut self) -> Result<Option<usize>, UsageError> {
+        match self.take(&["-j", "--jobs"])? {
+            Some(n) => match parse_number("--jobs", &n)? {
+                0 => Err(UsageError("--jobs must be at least 1".to_string())),
+                n => Ok(Some(n)),
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Removes a number which must be at least 1.
+    fn take_count(&mut self, names: &[&str], default: usize) -> Result<usize, UsageError> {
+        match self.take(names)? {
+            Some(n) => match parse_number(names[names.len() - 1], &n)? {
+                0 => Err(UsageError(format!(
+                    "{} must be at least 1",
+                    names[names.len() - 1]
+                ))),
+                n => Ok(n),
+            },
+            None => Ok(default),
+        }
+    }
+
+    /// Removes the options which say where to find puzzle inputs.
+    fn take_provider(&mut self, days: &Selection) -> Result<Provider, UsageError> {
+        let input = self.take(&["-i", "--input"])?;
+        let input_dir = self.take(&["--input-dir"])?;
+        if input.is_some() && days == &Selection::All {
+            return Err(UsageError(
+                "--input can only be used with a single --day".to_string(),
+            ));
+        }
+        let mut provider = Provider {
+            path: input.map(PathBuf::from),
+            ..Default::default()
+        };
+        if let Some(dir) = input_dir {
+            provider.dir = PathBuf::from(dir);
+        }
+        Ok(provider)
+    }
+
+    fn remove(&mut self, names: &[&str]) -> Option<(String, Option<String>)> {
+        let index = self
+            .0
+            .iter()
+            .position(|(n, _)| names.contains(&n.as_str()))?;
+        Some(self.0.remove(index))
+    }
+
+    /// Checks that every option was understood.
+    fn finish(self) -> Result<(), UsageError> {
+        match self.0.into_iter().next() {
+            Some((name, _)) if name.starts_with('-') => {
+                Err(UsageError(format!("unknown option '{}'", name)))
+            }
+            Some((name, _)) => Err(UsageError(format!("unexpected argument '{}'", name))),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn parses_single_day() {
+        assert_eq!(
+            Ok(Command::Run(Run {
+                days: Selection::Day(5),
+                part: Some(Part::Two),
+                input: Provider {
+                    path: Some(PathBuf::from("path/to/file")),
+                    ..Default::default()
+                },
+                format: Format::Text,
+                examples: None,
+                trace: false,
+                jobs: None,
+                batch: None,
+                cache: true,
+                memory: false,
+            })),
+            parse(args("run --day 5 --part 2 --input path/to/file"))
+        );
+    }
+
+    #[test]
+    fn parses_equals_and_short_forms() {
+        assert_eq!(
+            parse(args("run --day=5 --part=2 --format=json")),
+            parse(args("run -d 5 -p 2 -f json"))
+        );
+    }
+
+    #[test]
+    fn parses_all() {
+        assert_eq!(
+            Ok(Command::Run(Run {
+                days: Selection::All,
+                part: None,
+                input: Default::default(),
+                format: Format::Text,
+                examples: None,
+                trace: false,
+                jobs: None,
+                batch: None,
+                cache: true,
+                memory: false,
+            })),
+            parse(args("run --all"))
+        );
+    }
+
+    #[test]
+    fn parses_formats() {
+        let format = |s| match parse(args(s)) {
+            Ok(Command::Run(r)) => r.format,
+            other => panic!("unexpected parse result {:?}", other),
+        };
+        assert_eq!(Format::Json, format("run --all --format json"));
+        assert_eq!(Format::Csv, format("run --day 1 -f csv"));
+        assert_eq!(Format::Text, format("run --day 1 --format text"));
+    }
+
+    #[test]
+    fn parses_examples() {
+        let examples = |s| match parse(args(s)) {
+            Ok(Command::Run(r)) => r.examples,
+            other => panic!("unexpected parse result {:?}", other),
+        };
+        assert_eq!(Some(Examples::All), examples("run --all --example"));
+        assert_eq!(Some(Examples::All), examples("run --example --day 8"));
+        assert_eq!(
+            Some(Examples::Named("entry".to_string())),
+            examples("run --day 8 -e entry")
+        );
+        assert_eq!(None, examples("run --day 8"));
+    }
+
+    #[test]
+    fn parses_trace() {
+        match parse(args("run --day 2 --trace --example")) {
+            Ok(Command::Run(r)) => assert!(r.trace && r.examples.is_some()),
+            other => panic!("unexpected parse result {:?}", other),
+        }
+        assert!(parse(args("run --day 2 -t 1")).is_err());
+        match parse(args("run --all -m")) {
+            Ok(Command::Run(r)) => assert!(r.memory && !r.trace),
+            other => panic!("unexpected parse result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_batch() {
+        match parse(args("run --day 5 --inputs everyone/ -j 2")) {
+            Ok(Command::Run(r)) => {
+                assert_eq!(Some(PathBuf::from("everyone/")), r.batch);
+                assert_eq!(Some(2), r.jobs);
+            }
+            other => panic!("unexpected parse result {:?}", other),
+        }
+        assert!(parse(args("run --all --inputs everyone")).is_err());
+        assert!(parse(args("run --day 5 --inputs everyone --input x")).is_err());
+        assert!(parse(args("run --day 5 --inputs everyone --example")).is_err());
+    }
+
+    #[test]
+    fn parses_input_locations() {
+        let run = |s| match parse(args(s)) {
+            Ok(Command::Run(r)) => r.input,
+            other => panic!("unexpected parse result {:?}", other),
+        };
+        assert_eq!(Some(PathBuf::from("-")), run("run --day 1 --input -").path);
+        assert_eq!(PathBuf::from("mine"), run("run --all --input-dir mine").dir);
+    }
+
+    #[test]
+    fn parses_verify() {
+        assert_eq!(
+            Ok(Command::Verify(Verify {
+                days: Selection::All,
+                input: Default::default(),
+                answers: PathBuf::from("answers.toml"),
+                record: false,
+                jobs: None,
+            })),
+            parse(args("verify"))
+        );
+        match parse(args("verify --day 3 --answers mine.toml --record -j 2")) {
+            Ok(Command::Verify(v)) => {
+                assert_eq!(Selection::Day(3), v.days);
+                assert_eq!(Some(2), v.jobs);
+                assert_eq!(PathBuf::from("mine.toml"), v.answers);
+                assert!(v.record);
+            }
+            other => panic!("unexpected parse result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_bench() {
+        assert_eq!(
+            Ok(Command::Bench(Bench {
+                days: Selection::All,
+                input: Default::default(),
+                warmup: 3,
+                runs: 20,
+                save: None,
+                baseline: None,
+                jobs: 1,
+            })),
+            parse(args("bench"))
+        );
+        match parse(args(
+            "bench -d 5 --warmup 0 --runs 5 --save new.toml --baseline old.toml --jobs 3",
+        )) {
+            Ok(Command::Bench(b)) => {
+                assert_eq!(Selection::Day(5), b.days);
+                assert_eq!((0, 5, 3), (b.warmup, b.runs, b.jobs));
+                assert_eq!(Some(PathBuf::from("new.toml")), b.save);
+                assert_eq!(Some(PathBuf::from("old.toml")), b.baseline);
+            }
+            other => panic!("unexpected parse result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_generate() {
+        assert_eq!(
+            Ok(Command::Generate(Generate {
+                day: 4,
+                size: None,
+                seed: None,
+            })),
+            parse(args("generate --day 4"))
+        );
+        assert_eq!(
+            Ok(Command::Generate(Generate {
+                day: 1,
+                size: Some(100000),
+                seed: Some(u64::MAX),
+            })),
+            parse(args("generate -d 1 -s 100000 --seed 18446744073709551615"))
+        );
+    }
+
+    #[test]
+    fn parses_crosscheck() {
+        assert_eq!(
+            Ok(Command::Crosscheck(Crosscheck {
+                days: Selection::All,
+                input: Default::default(),
+                cases: 100,
+                size: 50,
+                seed: None,
+            })),
+            parse(args("crosscheck"))
+        );
+        match parse(args("crosscheck -d 5 --cases 0 -s 10 --seed 3")) {
+            Ok(Command::Crosscheck(c)) => {
+                assert_eq!(Selection::Day(5), c.days);
+                assert_eq!((0, 10, Some(3)), (c.cases, c.size, c.seed));
+            }
+            other => panic!("unexpected parse result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_cache() {
+        let cache = |s| match parse(args(s)) {
+            Ok(Command::Run(r)) => r.cache,
+            other => panic!("unexpected parse result {:?}", other),
+        };
+        assert!(cache("run --all"));
+        assert!(!cache("run --all --no-cache"));
+        assert_eq!(Ok(Command::ClearCache), parse(args("cache clear")));
+        assert!(parse(args("cache")).is_err());
+        assert!(parse(args("cache empty")).is_err());
+        assert!(parse(args("cache clear --all")).is_err());
+    }
+
+    #[test]
+    fn parses_report() {
+        assert_eq!(
+            Ok(Command::Report(Report {
+                input: Default::default(),
+                window: 3,
+                width: 60,
+                height: 12,
+            })),
+            parse(args("report"))
+        );
+        match parse(args("report -w 5 --width 20 --height 4 --input sweep.txt")) {
+            Ok(Command::Report(r)) => {
+                assert_eq!((5, 20, 4), (r.window, r.width, r.height));
+                assert_eq!(Some(PathBuf::from("sweep.txt")), r.input.path);
+            }
+            other => panic!("unexpected parse result {:?}", other),
+        }
+        assert!(parse(args("report --window 0")).is_err());
+        assert!(parse(args("report --day 2")).is_err());
+    }
+
+    #[test]
+    fn parses_dive() {
+        assert_eq!(
+            Ok(Command::Dive(Dive {
+                input: Default::default(),
+                model: "aimed".to_string(),
+                surface: Surface::Error,
+            })),
+            parse(args("dive"))
+        );
+        match parse(args("dive -M direct --surface clamp")) {
+            Ok(Command::Dive(d)) => {
+                assert_eq!(("direct", Surface::Clamp), (d.model.as_str(), d.surface))
+            }
+            other => panic!("unexpected parse result {:?}", other),
+        }
+        assert!(parse(args("dive --model drag")).is_err());
+        assert!(parse(args("dive --surface ignore")).is_err());
+    }
+
+    #[test]
+    fn parses_serve() {
+        assert_eq!(
+            Ok(Command::Serve(Serve { port: 2021 })),
+            parse(args("serve"))
+        );
+        assert_eq!(
+            Ok(Command::Serve(Serve { port: 8080 })),
+            parse(args("serve --port 8080"))
+        );
+        assert!(parse(args("serve --port 65536")).is_err());
+    }
+
+    #[test]
+    fn rejects_bad_usage() {
+        assert!(parse(args("")).is_err());
+        assert!(parse(args("walk")).is_err());
+        assert!(parse(args("run")).is_err());
+        assert!(parse(args("run --day five")).is_err());
+        assert!(parse(args("run --day 5 --part 3")).is_err());
+        assert!(parse(args("run --day 5 --format xml")).is_err());
+        assert!(parse(args("run --day 5 --example --input x")).is_err());
+        assert!(parse(args("run --day 5 --all")).is_err());
+        assert!(parse(args("run --all 5")).is_err());
+        assert!(parse(args("run --all --input x")).is_err());
+        assert!(parse(args("run --day")).is_err());
+        assert!(parse(args("run --day 5 --colour")).is_err());
+        assert!(parse(args("list --day 5")).is_err());
+        assert!(parse(args("bench --runs 0")).is_err());
+        assert!(parse(args("bench --all")).is_err());
+        assert!(parse(args("generate --size 10")).is_err());
+        assert!(parse(args("generate --day 1 --size 0")).is_err());
+        assert!(parse(args("generate --day 1 --seed -1")).is_err());
+        assert!(parse(args("crosscheck --size 0")).is_err());
+        assert!(parse(args("run --all --jobs 0")).is_err());
+    }
+}
@@ -0,0 +1,144 @@
+//! Checking the solvers against each day's [reference](crate::Puzzle::reference)
+//! implementation, on puzzle inputs and on [generated](crate::generate) ones.
+
+use crate::{
+    error::AocError,
+    generate::{self, Rng},
+    solution::{Answer, Part, Solution},
+};
+
+/// A part whose solver and reference gave different answers.
+#[derive(Debug, PartialEq)]
+pub struct Mismatch {
+    pub part: Part,
+    pub solver: Result<Answer, AocError>,
+    pub reference: Result<Answer, AocError>,
+}
+
+/// Solves both parts with the solver and with the reference, returning the
+/// parts which disagree. Inputs which both reject agree, whatever the errors.
+pub fn compare(solution: &dyn Solution, input: &str) -> Vec<Mismatch> {
+    Part::BOTH
+        .iter()
+        .filter_map(|&part| {
+            let solver = solution.solve(part, input);
+            let reference = solution.reference(part, input);
+            let agree = match (&solver, &reference) {
+                (Ok(s), Ok(r)) => s == r,
+                (Err(_), Err(_)) => true,
+                _ => false,
+            };
+            (!agree).then_some(Mismatch {
+                part,
+                solver,
+                reference,
+            })
+        })
+        .collect()
+}
+
+/// A generated input, which can be generated again with the same seed and size.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Case {
+    pub seed: u64,
+    pub size: usize,
+}
+impl Case {
+    /// The input for `day`, or `None` if there is no generator for the day.
+    pub fn input(&self, day: u32) -> Option<String> {
+        generate::generate(day, Some(self.size), self.seed)
+    }
+
+    /// The command which writes this input.
+    pub fn command(&self, day: u32) -> String {
+        format!(
+            "generate --day {} --size {} --seed {}",
+            day, self.size, self.seed
+        )
+    }
+}
+
+/// `count` cases with consecutive seeds from `seed`, and sizes from 1 to `max_size`.
+///
+/// Small inputs are as likely as large ones, since they are the easiest to
+/// reason about when the solvers disagree.
+pub fn cases(seed: u64, count: u32, max_size: usize) -> impl Iterator<Item = Case> {
+    (0..u64::from(count)).map(move |i| {
+        let seed = seed.wrapping_add(i);
+        Case {
+            seed,
+            size: 1 + Rng::new(!seed).below(max_size.max(1) as u64) as usize,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{day01, day02, day03, day07, examples::EXAMPLES, solution};
+
+    #[test]
+    fn references_agree_on_examples() {
+        for e in EXAMPLES {
+            let s = solution::find(e.day).unwrap();
+            assert_eq!(Vec::<Mismatch>::new(), compare(s, e.input), "day {}", e.day);
+        }
+    }
+
+    #[test]
+    fn references_agree_on_puzzle_inputs() {
+        for s in solution::DAYS {
+            assert_eq!(
+                Vec::<Mismatch>::new(),
+                compare(*s, s.input()),
+                "day {}",
+                s.day()
+            );
+        }
+    }
+
+    #[test]
+    fn references_agree_on_generated_inputs() {
+        for s in solution::DAYS {
+            for case in cases(0, 10, 20) {
+                let input = case.input(s.day()).unwrap();
+                assert_eq!(
+                    Vec::<Mismatch>::new(),
+                    compare(*s, &input),
+                    "{}",
+                    case.command(s.day())
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn agrees_on_edge_cases() {
+        // a depth which can't be parsed is rejected by both.
+        assert_eq!(Vec::<Mismatch>::new(), compare(&day01::Day01, "1\nx\n"));
+        // rising above the surface is rejected by both in part 1, even if it dives
+        // again later; in part 2 only the aim rises, which both allow.
+        assert_eq!(
+            Vec::<Mismatch>::new(),
+            compare(&day02::Day02, "forward 5\nup 3\ndown 9\n")
+        );
+        assert_eq!(Vec::<Mismatch>::new(), compare(&day03::Day03, "0\n1\n"));
+        assert_eq!(Vec::<Mismatch>::new(), compare(&day03::Day03, "101\n"));
+        assert_eq!(Vec::<Mismatch>::new(), compare(&day07::Day07, "5\n"));
+    }
+
+    #[test]
+    fn makes_reproducible_cases() {
+        let all = cases(u64::MAX, 5, 10).collect::<Vec<_>>();
+        assert_eq!(all, cases(u64::MAX, 5, 10).collect::<Vec<_>>());
+        assert_eq!(
+            vec![u64::MAX, 0, 1, 2, 3],
+            all.iter().map(|c| c.seed).collect::<Vec<_>>()
+        );
+        assert!(all.iter().all(|c| (1..=10).contains(&c.size)));
+        assert_eq!(
+            "generate --day 3 --size 7 --seed 9",
+            Case { seed: 9, size: 7 }.command(3)
+        );
+    }
+}
@@ -1,33 +1,277 @@
-pub fn part1() -> u32 {
-    let mut l = numbers().peekable();
-    let mut count = 0;
-    while let Some(line) = l.next() {
-        if let Some(next) = l.peek() {
-            let (u, v) = (line, next);
-            let increment = if v > &u { 1 } else { 0 };
-            count += increment;
+use std::{cmp::Ordering, collections::VecDeque, io::BufRead};
+
+use crate::{
+    error::{parse_number, AocError},
+    generate::Rng,
+    solution::{Answer, Part, Puzzle},
+    stream::{lines, until_error},
+};
+
+pub mod report;
+
+pub struct Day01;
+impl Puzzle for Day01 {
+    const DAY: u32 = 1;
+    const NAME: &'static str = "Sonar Sweep";
+    const INPUT: &'static str = INPUT;
+    const VERSION: u32 = 2;
+
+    type Parsed<'a> = Vec<u32>;
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, AocError> {
+        parse_depths(input)
+    }
+    fn part1(depths: &Vec<u32>) -> Result<Answer, AocError> {
+        Ok(part1(depths.iter().copied()))
+    }
+    fn part2(depths: &Vec<u32>) -> Result<Answer, AocError> {
+        Ok(part2(depths.iter().copied()))
+    }
+    fn reference(depths: &Vec<u32>, part: Part) -> Result<Answer, AocError> {
+        reference(depths, part)
+    }
+}
+
+/// Counts the depths deeper than the one before.
+pub fn part1(depths: impl Iterator<Item = u32>) -> Answer {
+    count_increases(depths, 1, 1) as Answer
+}
+
+/// Counts the sums of three depths deeper than the sum before.
+pub fn part2(depths: impl Iterator<Item = u32>) -> Answer {
+    count_increases(depths, 3, 1) as Answer
+}
+
+/// How a window's sum must compare with an earlier one to be counted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Change {
+    Increase,
+    /// An increase, or no change.
+    NonDecrease,
+    Decrease,
+    /// A decrease, or no change.
+    NonIncrease,
+    Equal,
+}
+impl Change {
+    fn matches(self, ordering: Ordering) -> bool {
+        match self {
+            Change::Increase => ordering == Ordering::Greater,
+            Change::NonDecrease => ordering != Ordering::Less,
+            Change::Decrease => ordering == Ordering::Less,
+            Change::NonIncrease => ordering != Ordering::Greater,
+            Change::Equal => ordering == Ordering::Equal,
         }
     }
-    count
 }
 
-pub fn part2() -> u32 {
-    const LENGTH: usize = 3;
-    let mut v = Vec::with_capacity(LENGTH);
+/// Counts the sums of `window` consecutive depths which are greater than the
+/// sum of the window `lag` depths earlier.
+pub fn count_increases(depths: impl Iterator<Item = u32>, window: usize, lag: usize) -> usize {
+    count_changes(depths, window, lag, Change::Increase)
+}
+
+/// Counts the sums of `window` consecutive depths whose comparison with the
+/// sum of the window `lag` depths earlier is `change`.
+///
+/// Each depth takes constant time, whatever the window, and only the last
+/// `window` depths and `lag` sums are held. Both `window` and `lag` must be at least 1.
+pub fn count_changes(
+    depths: impl Iterator<Item = u32>,
+    window: usize,
+    lag: usize,
+    change: Change,
+) -> usize {
+    assert!(
+        window > 0 && lag > 0,
+        "Windows must hold a depth, and be compared with an earlier one"
+    );
+    // the depths in the current window, and the sums of the `lag` windows before it.
+    let mut depths_held = VecDeque::with_capacity(window);
+    let mut sums = VecDeque::with_capacity(lag);
+    let mut sum = 0u64;
     let mut count = 0;
-    for (index, n) in numbers().enumerate() {
-        if v.len() < LENGTH {
-            v.push(n);
-        } else {
-            let old_sum: u32 = v.iter().sum();
-            v[index % LENGTH] = n;
-            count += if v.iter().sum::<u32>() > old_sum { 1 } else { 0 };
+    for depth in depths {
+        let depth = u64::from(depth);
+        depths_held.push_back(depth);
+        sum += depth;
+        if depths_held.len() > window {
+            sum -= depths_held.pop_front().expect("the window is full");
+        }
+        if depths_held.len() < window {
+            continue;
+        }
+        if sums.len() == lag {
+            let earlier = sums.pop_front().expect("the earlier sums are full");
+            if change.matches(sum.cmp(&earlier)) {
+                count += 1;
+            }
         }
+        sums.push_back(sum);
     }
     count
 }
 
-const INPUT: &str = include_str!("day01/input.txt");
-fn numbers() -> impl Iterator<Item = u32> {
-    INPUT.lines().map(|s| s.parse::<u32>().unwrap())
+/// Solves part 1 a line at a time, without holding the input in memory.
+pub fn part1_reader(reader: impl BufRead) -> Result<Answer, AocError> {
+    let mut error = None;
+    let answer = part1(until_error(read_depths(reader), &mut error));
+    error.map_or(Ok(answer), Err)
+}
+/// Solves part 2 a line at a time, without holding the input in memory.
+pub fn part2_reader(reader: impl BufRead) -> Result<Answer, AocError> {
+    let mut error = None;
+    let answer = part2(until_error(read_depths(reader), &mut error));
+    error.map_or(Ok(answer), Err)
+}
+
+pub const INPUT: &str = include_str!("day01/input.txt");
+pub const EXAMPLE: &str = include_str!("day01/example.txt");
+/// Parses one depth from each line of the input text.
+pub fn parse_depths(input: &str) -> Result<Vec<u32>, AocError> {
+    read_depths(input.as_bytes()).collect()
+}
+
+/// Reads one depth from each line.
+pub fn read_depths(reader: impl BufRead) -> impl Iterator<Item = Result<u32, AocError>> {
+    lines(Day01::DAY, reader).map(|line| {
+        let line = line?;
+        parse_number(Day01::DAY, &line.text, &line.text).map_err(|e| line.locate(e))
+    })
+}
+
+/// Writes `size` depths which mostly, but not always, get deeper.
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let mut depth = rng.range(100..=200);
+    let mut output = String::new();
+    for _ in 0..size {
+        output.push_str(&depth.to_string());
+        output.push('\n');
+        depth = (depth + rng.range(0..=40)).saturating_sub(10);
+    }
+    output
+}
+
+/// Compares every window's sum with the one before, the slow way.
+fn reference(depths: &[u32], part: Part) -> Result<Answer, AocError> {
+    let width = match part {
+        Part::One => 1,
+        Part::Two => 3,
+    };
+    let sums = depths
+        .windows(width)
+        .map(|w| w.iter().map(|&d| u64::from(d)).sum::<u64>())
+        .collect::<Vec<_>>();
+    Ok((1..sums.len()).filter(|&i| sums[i] > sums[i - 1]).count() as Answer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solution::Solution;
+
+    #[test]
+    fn solves_example() {
+        let depths = parse_depths(EXAMPLE).unwrap();
+        assert_eq!(7, part1(depths.iter().copied()));
+        assert_eq!(5, part2(depths.into_iter()));
+        assert_eq!(Ok(7), Day01.part1(EXAMPLE));
+        assert_eq!(Ok(5), Day01.part2(EXAMPLE));
+    }
+
+    #[test]
+    fn rejects_bad_depths() {
+        assert_eq!(
+            Err(AocError::parse(
+                1,
+                2,
+                1,
+                "'200m' is not a valid number: invalid digit found in string"
+            )),
+            parse_depths("199\n200m\n")
+        );
+        assert!(matches!(
+            parse_depths("199\n-5\n"),
+            Err(AocError::Parse { line: 2, .. })
+        ));
+        assert_eq!(Ok(vec![]), parse_depths(""));
+    }
+
+    /// Sums every window and compares each with the one `lag` before, the slow way.
+    fn naive(depths: &[u32], window: usize, lag: usize, change: Change) -> usize {
+        let sums = depths
+            .windows(window)
+            .map(|w| w.iter().map(|&d| u64::from(d)).sum::<u64>())
+            .collect::<Vec<_>>();
+        (lag..sums.len())
+            .filter(|&i| change.matches(sums[i].cmp(&sums[i - lag])))
+            .count()
+    }
+
+    #[test]
+    fn counts_any_window() {
+        let depths = parse_depths(EXAMPLE).unwrap();
+        let changes = [
+            Change::Increase,
+            Change::NonDecrease,
+            Change::Decrease,
+            Change::NonIncrease,
+            Change::Equal,
+        ];
+        for window in 1..=12 {
+            for lag in 1..=12 {
+                for &change in &changes {
+                    assert_eq!(
+                        naive(&depths, window, lag, change),
+                        count_changes(depths.iter().copied(), window, lag, change),
+                        "window {}, lag {}, {:?}",
+                        window,
+                        lag,
+                        change
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn counts_special_cases() {
+        let depths = parse_depths(EXAMPLE).unwrap();
+        let count =
+            |window, lag, change| count_changes(depths.iter().copied(), window, lag, change);
+        // comparing sums of three is comparing depths three apart.
+        assert_eq!(5, count(3, 1, Change::Increase));
+        assert_eq!(5, count(1, 3, Change::Increase));
+        // every pair of depths differs.
+        assert_eq!(0, count(1, 1, Change::Equal));
+        assert_eq!(7, count(1, 1, Change::NonDecrease));
+        assert_eq!(2, count(1, 1, Change::Decrease));
+        assert_eq!(0, count_increases([5, 5, 5].iter().copied(), 1, 1));
+        assert_eq!(
+            2,
+            count_changes([5, 5, 5].iter().copied(), 1, 1, Change::Equal)
+        );
+        assert_eq!(0, count_increases(std::iter::empty(), 4, 2));
+    }
+
+    #[test]
+    fn solves_from_reader() {
+        assert_eq!(Ok(7), part1_reader(EXAMPLE.as_bytes()));
+        assert_eq!(Ok(5), part2_reader(EXAMPLE.as_bytes()));
+        assert_eq!(
+            Err(AocError::parse(
+                1,
+                3,
+                1,
+                "'2o8' is not a valid number: invalid digit found in string"
+            )),
+            part1_reader("199\n200\n2o8\n210\n".as_bytes())
+        );
+    }
+
+    #[test]
+    fn reader_matches_str() {
+        let input = INPUT.replace('\n', "\r\n");
+        assert_eq!(Day01.part1(INPUT), part1_reader(input.as_bytes()));
+        assert_eq!(Day01.part2(INPUT), part2_reader(input.as_bytes()));
+    }
 }
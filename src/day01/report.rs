@@ -0,0 +1,375 @@
+//! A closer look at a sonar sweep than counting increases: its extremes,
+//! runs, jumps, moving averages and a rough profile of the sea floor.
+//!
+//! Lines count from 1, as they do in parse errors.
+
+use std::fmt::{Display, Write};
+
+/// A depth, and the line it was read from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Reading {
+    pub line: usize,
+    pub depth: u32,
+}
+
+/// Consecutive depths which each rise (or each fall) from the one before.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Streak {
+    pub first_line: usize,
+    /// How many depths are in the streak, including the first.
+    pub length: usize,
+}
+
+/// The change from one depth to the next.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Jump {
+    /// The line of the second depth.
+    pub line: usize,
+    pub from: u32,
+    pub to: u32,
+}
+impl Jump {
+    pub fn size(&self) -> u32 {
+        self.from.max(self.to) - self.from.min(self.to)
+    }
+}
+
+/// The average of each run of `window` consecutive depths.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MovingAverage {
+    pub window: usize,
+    /// The average of the window starting at each line, in order.
+    pub averages: Vec<f64>,
+}
+impl MovingAverage {
+    /// The shallowest and deepest averages, with the first line of their window.
+    pub fn extremes(&self) -> Option<((usize, f64), (usize, f64))> {
+        let mut averages = self.averages.iter().enumerate().map(|(i, &a)| (i + 1, a));
+        let first = averages.next()?;
+        Some(averages.fold((first, first), |(low, high), a| {
+            (
+                if a.1 < low.1 { a } else { low },
+                if a.1 > high.1 { a } else { high },
+            )
+        }))
+    }
+}
+
+/// Everything the report says about a sweep. Ties go to the earliest line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    pub depths: usize,
+    pub shallowest: Option<Reading>,
+    pub deepest: Option<Reading>,
+    pub longest_rise: Option<Streak>,
+    pub longest_fall: Option<Streak>,
+    pub largest_jump: Option<Jump>,
+    /// The lines of depths shallower than those either side.
+    pub local_minima: Vec<usize>,
+    /// The lines of depths deeper than those either side.
+    pub local_maxima: Vec<usize>,
+    pub moving_average: MovingAverage,
+}
+
+/// Analyses the depths, averaging them over `window` depths at a time.
+pub fn report(depths: &[u32], window: usize) -> Report {
+    let readings = depths
+        .iter()
+        .enumerate()
+        .map(|(i, &depth)| Reading { line: i + 1, depth });
+    let interior = |keep: fn(u32, u32, u32) -> bool| {
+        depths
+            .windows(3)
+            .enumerate()
+            .filter(|(_, w)| keep(w[0], w[1], w[2]))
+            .map(|(i, _)| i + 2)
+            .collect()
+    };
+    Report {
+        depths: depths.len(),
+        shallowest: readings.clone().min_by_key(|r| r.depth),
+        deepest: readings.min_by_key(|r| std::cmp::Reverse(r.depth)),
+        longest_rise: longest_streak(depths, |a, b| b > a),
+        longest_fall: longest_streak(depths, |a, b| b < a),
+        largest_jump: depths
+            .windows(2)
+            .enumerate()
+            .map(|(i, w)| Jump {
+                line: i + 2,
+                from: w[0],
+                to: w[1],
+            })
+            .rev()
+            .max_by_key(Jump::size),
+        local_minima: interior(|before, d, after| d < before && d < after),
+        local_maxima: interior(|before, d, after| d > before && d > after),
+        moving_average: moving_average(depths, window),
+    }
+}
+
+/// The longest run of depths in which every pair of neighbours satisfies `step`.
+fn longest_streak(depths: &[u32], step: fn(u32, u32) -> bool) -> Option<Streak> {
+    let mut best: Option<Streak> = None;
+    let mut first = 0;
+    for i in 1..=depths.len() {
+        if i < depths.len() && step(depths[i - 1], depths[i]) {
+            continue;
+        }
+        let length = i - first;
+        if length > 1 && best.is_none_or(|b| length > b.length) {
+            best = Some(Streak {
+                first_line: first + 1,
+                length,
+            });
+        }
+        first = i;
+    }
+    best
+}
+
+/// The average of each run of `window` depths, in constant time per depth.
+/// `window` must be at least 1.
+pub fn moving_average(depths: &[u32], window: usize) -> MovingAverage {
+    assert!(window > 0, "Can't average fewer than one depth");
+    let mut averages = Vec::with_capacity(depths.len().saturating_sub(window - 1));
+    let mut sum = 0u64;
+    for (i, &depth) in depths.iter().enumerate() {
+        sum += u64::from(depth);
+        if i >= window {
+            sum -= u64::from(depths[i - window]);
+        }
+        if i + 1 >= window {
+            averages.push(sum as f64 / window as f64);
+        }
+    }
+    MovingAverage { window, averages }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (shallowest, deepest) = match (self.shallowest, self.deepest) {
+            (Some(s), Some(d)) => (s, d),
+            _ => return writeln!(f, "No depths"),
+        };
+        let streak = |s: Option<Streak>| match s {
+            Some(s) => format!(
+                "{} depths, lines {} to {}",
+                s.length,
+                s.first_line,
+                s.first_line + s.length - 1
+            ),
+            None => "none".to_string(),
+        };
+        let lines = |lines: &[usize]| {
+            const SHOWN: usize = 5;
+            let mut output = lines.len().to_string();
+            if !lines.is_empty() {
+                let first = lines.iter().take(SHOWN).map(|l| l.to_string());
+                write!(
+                    output,
+                    ", at lines {}",
+                    first.collect::<Vec<_>>().join(", ")
+                )
+                .unwrap();
+                if lines.len() > SHOWN {
+                    output.push_str(", ...");
+                }
+            }
+            output
+        };
+        writeln!(f, "Depths:         {}", self.depths)?;
+        writeln!(
+            f,
+            "Shallowest:     {} (line {})",
+            shallowest.depth, shallowest.line
+        )?;
+        writeln!(
+            f,
+            "Deepest:        {} (line {})",
+            deepest.depth, deepest.line
+        )?;
+        writeln!(f, "Longest rise:   {}", streak(self.longest_rise))?;
+        writeln!(f, "Longest fall:   {}", streak(self.longest_fall))?;
+        match self.largest_jump {
+            Some(j) => writeln!(
+                f,
+                "Largest jump:   {} ({} to {}, lines {} to {})",
+                j.size(),
+                j.from,
+                j.to,
+                j.line - 1,
+                j.line
+            )?,
+            None => writeln!(f, "Largest jump:   none")?,
+        }
+        writeln!(f, "Local minima:   {}", lines(&self.local_minima))?;
+        writeln!(f, "Local maxima:   {}", lines(&self.local_maxima))?;
+        let window = self.moving_average.window;
+        match self.moving_average.extremes() {
+            Some(((low_line, low), (high_line, high))) => writeln!(
+                f,
+                "Moving average: {:.1} to {:.1} over {} depths (lines {} to {}, and {} to {})",
+                low,
+                high,
+                window,
+                low_line,
+                low_line + window - 1,
+                high_line,
+                high_line + window - 1
+            ),
+            None => writeln!(f, "Moving average: fewer than {} depths", window),
+        }
+    }
+}
+
+/// Draws the depths as columns hanging from the surface, `width` columns
+/// across and `height` rows down, with the shallowest and deepest depths
+/// labelled on the left and the line numbers beneath.
+///
+/// Each column is the average of an equal share of the depths.
+pub fn chart(depths: &[u32], width: usize, height: usize) -> String {
+    let (min, max) = match (depths.iter().min(), depths.iter().max()) {
+        (Some(&min), Some(&max)) => (min, max),
+        _ => return String::new(),
+    };
+    let columns = width.clamp(1, depths.len());
+    let height = height.max(1);
+    // how many rows each column reaches down, from 1 to `height`.
+    let levels = (0..columns)
+        .map(|c| {
+            let bucket = &depths[c * depths.len() / columns..(c + 1) * depths.len() / columns];
+            let mean = bucket.iter().map(|&d| f64::from(d)).sum::<f64>() / bucket.len() as f64;
+            let fraction = match max - min {
+                0 => 0.0,
+                range => (mean - f64::from(min)) / f64::from(range),
+            };
+            1 + (fraction * (height - 1) as f64).round() as usize
+        })
+        .collect::<Vec<_>>();
+
+    let (top, bottom) = (min.to_string(), max.to_string());
+    let label_width = top.len().max(bottom.len());
+    let mut output = String::new();
+    for row in 0..height {
+        let label = match row {
+            0 => top.as_str(),
+            r if r == height - 1 => bottom.as_str(),
+            _ => "",
+        };
+        let cells = levels
+            .iter()
+            .map(|&level| if row < level { '#' } else { ' ' })
+            .collect::<String>();
+        let line = format!("{:>width$} |{}", label, cells, width = label_width);
+        writeln!(output, "{}", line.trim_end()).unwrap();
+    }
+    writeln!(
+        output,
+        "{:>width$} +{}",
+        "",
+        "-".repeat(columns),
+        width = label_width
+    )
+    .unwrap();
+    let last = depths.len().to_string();
+    let gap = columns.saturating_sub(1 + last.len());
+    let axis = match depths.len() {
+        1 => "1".to_string(),
+        _ => format!("1{}{}", " ".repeat(gap.max(1)), last),
+    };
+    writeln!(output, "{:>width$}  {}", "", axis, width = label_width).unwrap();
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day01::{parse_depths, EXAMPLE};
+
+    #[test]
+    fn reports_example() {
+        let depths = parse_depths(EXAMPLE).unwrap();
+        let r = report(&depths, 3);
+        assert_eq!(10, r.depths);
+        assert_eq!(
+            (
+                Some(Reading {
+                    line: 1,
+                    depth: 199
+                }),
+                Some(Reading {
+                    line: 8,
+                    depth: 269
+                })
+            ),
+            (r.shallowest, r.deepest)
+        );
+        assert_eq!(
+            Some(Streak {
+                first_line: 1,
+                length: 4
+            }),
+            r.longest_rise
+        );
+        assert_eq!(
+            Some(Streak {
+                first_line: 4,
+                length: 2
+            }),
+            r.longest_fall
+        );
+        assert_eq!(
+            Some(Jump {
+                line: 7,
+                from: 207,
+                to: 240
+            }),
+            r.largest_jump
+        );
+        assert_eq!((vec![5, 9], vec![4, 8]), (r.local_minima, r.local_maxima));
+        assert_eq!(8, r.moving_average.averages.len());
+        assert_eq!(
+            Some(((1, 607.0 / 3.0), (8, 264.0))),
+            r.moving_average.extremes()
+        );
+    }
+
+    #[test]
+    fn reports_short_sweeps() {
+        let r = report(&[], 3);
+        assert_eq!("No depths\n", r.to_string());
+        let r = report(&[5, 5], 3);
+        assert_eq!((None, None), (r.longest_rise, r.longest_fall));
+        assert_eq!(Some(0), r.largest_jump.map(|j| j.size()));
+        assert_eq!(None, r.moving_average.extremes());
+        assert!(r
+            .to_string()
+            .contains("Moving average: fewer than 3 depths"));
+    }
+
+    #[test]
+    fn averages_any_window() {
+        let depths = [1, 2, 3, 4, 10];
+        assert_eq!(
+            vec![1.0, 2.0, 3.0, 4.0, 10.0],
+            moving_average(&depths, 1).averages
+        );
+        assert_eq!(vec![2.5, 4.75], moving_average(&depths, 4).averages);
+        assert!(moving_average(&depths, 6).averages.is_empty());
+    }
+
+    #[test]
+    fn draws_chart() {
+        assert_eq!(
+            " 0 |###\n   | ##\n10 |  #\n   +---\n    1 3\n",
+            chart(&[0, 5, 10], 60, 3)
+        );
+        // two depths to each column.
+        assert_eq!(
+            "1 |##\n  | #\n4 | #\n  +--\n   1 4\n",
+            chart(&[1, 1, 4, 4], 2, 3)
+        );
+        assert_eq!("7 |#\n  +-\n   1\n", chart(&[7], 10, 1));
+        assert_eq!("", chart(&[], 10, 5));
+    }
+}
@@ -1,81 +1,362 @@
-pub fn part1() -> u32 {
-    let final_location =
-        instructions().fold(Default::default(), |prev: Location, cur| prev.add(&cur));
-    final_location.x * final_location.z
-}
-pub fn part2() -> u32 {
-    let final_location =
-        instructions().fold(Default::default(), |prev: Location2, cur| prev.add(&cur));
-    final_location.x * final_location.z
-}
-
-const INPUT: &str = include_str!("day02/input.txt");
-fn instructions() -> impl Iterator<Item = Instruction> {
-    INPUT.lines().map(|s| {
-        let (dir, num) = s.split_once(' ').unwrap();
-        let value: u32 = num.parse().unwrap();
-        match dir {
-            "forward" => Instruction::Forward(value),
-            "up" => Instruction::Up(value),
-            "down" => Instruction::Down(value),
-            _ => panic!("Unexpected instruction"),
-        }
+use std::{borrow::Borrow, convert::TryFrom, fmt::Display, io::BufRead};
+
+use crate::{
+    error::{parse_number, AocError},
+    generate::Rng,
+    solution::{Answer, Part, Puzzle},
+    stream::lines,
+    trace,
+};
+
+pub mod model;
+
+pub use model::Surface;
+use model::{Aimed, Direct, Position, SubmarineModel, Violation};
+
+pub struct Day02;
+impl Puzzle for Day02 {
+    const DAY: u32 = 2;
+    const NAME: &'static str = "Dive!";
+    const INPUT: &'static str = INPUT;
+    const VERSION: u32 = 3;
+
+    type Parsed<'a> = Vec<Instruction>;
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, AocError> {
+        parse_instructions(input)
+    }
+    fn part1(instructions: &Vec<Instruction>) -> Result<Answer, AocError> {
+        part1(instructions.iter())
+    }
+    fn part2(instructions: &Vec<Instruction>) -> Result<Answer, AocError> {
+        part2(instructions.iter())
+    }
+    fn reference(instructions: &Vec<Instruction>, part: Part) -> Result<Answer, AocError> {
+        reference(instructions, part)
+    }
+}
+
+pub fn part1<'a>(instructions: impl Iterator<Item = &'a Instruction>) -> Result<Answer, AocError> {
+    part1_with(instructions, Surface::Error)
+}
+pub fn part2<'a>(instructions: impl Iterator<Item = &'a Instruction>) -> Result<Answer, AocError> {
+    part2_with(instructions, Surface::Error)
+}
+
+/// Solves part 1, doing as `surface` says if the submarine rises above the surface.
+pub fn part1_with<'a>(
+    instructions: impl Iterator<Item = &'a Instruction>,
+    surface: Surface,
+) -> Result<Answer, AocError> {
+    dive::<Direct>(instructions, surface)?.answer()
+}
+/// Solves part 2, doing as `surface` says if the submarine rises above the surface.
+pub fn part2_with<'a>(
+    instructions: impl Iterator<Item = &'a Instruction>,
+    surface: Surface,
+) -> Result<Answer, AocError> {
+    dive::<Aimed>(instructions, surface)?.answer()
+}
+
+/// Follows the instructions with any model, returning where the submarine ends up.
+pub fn dive<'a, M: SubmarineModel>(
+    instructions: impl Iterator<Item = &'a Instruction>,
+    surface: Surface,
+) -> Result<Position, AocError> {
+    follow::<M, _>(instructions.map(Ok), surface).map(|m| m.position())
+}
+
+/// Moves the submarine by each instruction in turn, failing at the first
+/// which it can't follow.
+fn follow<M: SubmarineModel, I: Borrow<Instruction>>(
+    instructions: impl Iterator<Item = Result<I, AocError>>,
+    surface: Surface,
+) -> Result<M, AocError> {
+    instructions
+        .enumerate()
+        .try_fold(M::default(), |prev, (index, instruction)| {
+            let instruction = instruction?;
+            let instruction = instruction.borrow();
+            let next = prev.step(instruction, surface).map_err(|violation| {
+                let problem = match violation {
+                    Violation::AboveSurface => "takes the submarine above the surface",
+                    Violation::Overflow => "takes the submarine too far to count",
+                };
+                AocError::unsolvable(
+                    Day02::DAY,
+                    format!("instruction {} ({}) {}", index + 1, instruction, problem),
+                )
+            })?;
+            trace::emit("position", || {
+                let Position { x, depth } = next.position();
+                let mut fields = vec![
+                    ("instruction", instruction.to_string()),
+                    ("x", x.to_string()),
+                    ("depth", depth.to_string()),
+                ];
+                fields.extend(next.state());
+                fields
+            });
+            Ok(next)
+        })
+}
+
+pub const INPUT: &str = include_str!("day02/input.txt");
+pub const EXAMPLE: &str = include_str!("day02/example.txt");
+/// Solves part 1 a line at a time, without holding the input in memory.
+pub fn part1_reader(reader: impl BufRead) -> Result<Answer, AocError> {
+    let direct = follow::<Direct, _>(read_instructions(reader), Surface::Error)?;
+    direct.position().answer()
+}
+/// Solves part 2 a line at a time, without holding the input in memory.
+pub fn part2_reader(reader: impl BufRead) -> Result<Answer, AocError> {
+    let aimed = follow::<Aimed, _>(read_instructions(reader), Surface::Error)?;
+    aimed.position().answer()
+}
+
+/// Parses one instruction from each line of the input text.
+pub fn parse_instructions(input: &str) -> Result<Vec<Instruction>, AocError> {
+    read_instructions(input.as_bytes()).collect()
+}
+
+/// Reads one instruction from each line.
+pub fn read_instructions(
+    reader: impl BufRead,
+) -> impl Iterator<Item = Result<Instruction, AocError>> {
+    lines(Day02::DAY, reader).map(|line| {
+        let line = line?;
+        instruction(&line.text).map_err(|e| line.locate(e))
     })
 }
 
-#[derive(Clone, Copy, Debug, Default)]
-struct Location {
-    x: u32,
-    z: u32,
-}
-
-#[derive(Clone, Copy, Debug, Default)]
-struct Location2 {
-    x: u32,
-    z: u32,
-    aim: u32,
-}
-
-impl Location {
-    fn add(&self, instruction: &Instruction) -> Self {
-        match instruction {
-            Instruction::Forward(f) => Self {
-                x: self.x + f,
-                z: self.z,
-            },
-            Instruction::Down(d) => Self {
-                x: self.x,
-                z: self.z + d,
-            },
-            Instruction::Up(u) => Self {
-                x: self.x,
-                z: self.z - u,
-            },
+fn instruction(s: &str) -> Result<Instruction, AocError> {
+    let (dir, num) = s.split_once(' ').ok_or_else(|| {
+        AocError::at(
+            Day02::DAY,
+            s,
+            &s[s.len()..],
+            "expected a direction and a distance separated by a space",
+        )
+    })?;
+    let value: u32 = parse_number(Day02::DAY, s, num)?;
+    match dir {
+        "forward" => Ok(Instruction::Forward(value)),
+        "up" => Ok(Instruction::Up(value)),
+        "down" => Ok(Instruction::Down(value)),
+        _ => Err(AocError::at(
+            Day02::DAY,
+            s,
+            dir,
+            format!("unexpected instruction '{}'", dir),
+        )),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Instruction {
+    Forward(u32),
+    Down(u32),
+    Up(u32),
+}
+/// As written in the input, such as `forward 5`.
+impl Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Instruction::Forward(v) => write!(f, "forward {}", v),
+            Instruction::Down(v) => write!(f, "down {}", v),
+            Instruction::Up(v) => write!(f, "up {}", v),
         }
     }
 }
-impl Location2 {
-    fn add(&self, instruction: &Instruction) -> Self {
-        match instruction {
-            Instruction::Forward(f) => Self {
-                x: self.x + f,
-                z: self.z + self.aim * f,
-                ..*self
-            },
-            Instruction::Up(u) => Self {
-                aim: self.aim - u,
-                ..*self
-            },
-            Instruction::Down(d) => Self {
-                aim: self.aim + d,
-                ..*self
-            },
+
+/// Writes `size` instructions, none of which take the submarine (or its aim) above the surface.
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    // keeping the aim shallow keeps the answers small enough to multiply.
+    const MAX_AIM: u32 = 100;
+    let mut aim = 0;
+    let mut output = String::new();
+    for _ in 0..size {
+        let value = rng.range(1..=9);
+        let direction = match rng.below(3) {
+            0 if value <= aim => "up",
+            1 if aim + value <= MAX_AIM => "down",
+            _ => "forward",
+        };
+        match direction {
+            "up" => aim -= value,
+            "down" => aim += value,
+            _ => (),
         }
+        output.push_str(&format!("{} {}\n", direction, value));
     }
+    output
 }
 
-enum Instruction {
-    Forward(u32),
-    Down(u32),
-    Up(u32),
+/// Follows the instructions with 128-bit numbers, so that nothing can overflow.
+fn reference(instructions: &[Instruction], part: Part) -> Result<Answer, AocError> {
+    let (mut x, mut depth, mut aim) = (0i128, 0i128, 0i128);
+    for (index, i) in instructions.iter().enumerate() {
+        let (forward, down) = match *i {
+            Instruction::Forward(v) => (i128::from(v), 0),
+            Instruction::Down(v) => (0, i128::from(v)),
+            Instruction::Up(v) => (0, -i128::from(v)),
+        };
+        x += forward;
+        match part {
+            Part::One => depth += down,
+            Part::Two => {
+                aim += down;
+                depth += aim * forward;
+            }
+        }
+        if depth < 0 {
+            return Err(AocError::unsolvable(
+                Day02::DAY,
+                format!(
+                    "instruction {} ({}) takes the submarine above the surface",
+                    index + 1,
+                    i
+                ),
+            ));
+        }
+    }
+    Answer::try_from(x * depth)
+        .map_err(|_| AocError::unsolvable(Day02::DAY, "the submarine ended above the surface"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solution::Solution;
+
+    #[test]
+    fn solves_example() {
+        let instructions = parse_instructions(EXAMPLE).unwrap();
+        assert_eq!(Instruction::Forward(5), instructions[0]);
+        assert_eq!(Ok(150), part1(instructions.iter()));
+        assert_eq!(Ok(900), part2(instructions.iter()));
+        assert_eq!(Ok(150), Day02.part1(EXAMPLE));
+        assert_eq!(Ok(900), Day02.part2(EXAMPLE));
+    }
+
+    #[test]
+    fn rejects_bad_instructions() {
+        assert_eq!(
+            Err(AocError::parse(
+                2,
+                3,
+                4,
+                "'x' is not a valid number: invalid digit found in string"
+            )),
+            parse_instructions("forward 5\ndown 5\nup x\n")
+        );
+        assert!(matches!(
+            parse_instructions("forward\n"),
+            Err(AocError::Parse { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_leaving_the_water() {
+        let instructions = parse_instructions("forward 5\nup 3\n").unwrap();
+        assert_eq!(
+            Err(AocError::unsolvable(
+                2,
+                "instruction 2 (up 3) takes the submarine above the surface"
+            )),
+            part1(instructions.iter())
+        );
+        assert!(part1_reader("forward 5\nup 3\n".as_bytes()).is_err());
+        // only the aim is above the surface, which is allowed.
+        assert_eq!(Ok(0), part2(instructions.iter()));
+    }
+
+    #[test]
+    fn rejects_overflow() {
+        let far = parse_instructions("down 4000000000\ndown 4000000000\n").unwrap();
+        assert_eq!(Ok(0), part1(far.iter()));
+        let far = parse_instructions(
+            "down 4000000000\ndown 4000000000\ndown 4000000000\nforward 4000000000\n",
+        )
+        .unwrap();
+        assert_eq!(
+            Err(AocError::unsolvable(
+                2,
+                "instruction 4 (forward 4000000000) takes the submarine too far to count"
+            )),
+            part2(far.iter())
+        );
+        let wide = parse_instructions("down 4000000000\nforward 4000000000\n").unwrap();
+        assert_eq!(
+            Err(AocError::unsolvable(2, "the answer is too large to count")),
+            part1(wide.iter())
+        );
+    }
+
+    #[test]
+    fn follows_surface_policy() {
+        let part1_instructions = parse_instructions("forward 5\nup 3\ndown 9\n").unwrap();
+        let part1_policy = |surface| part1_with(part1_instructions.iter(), surface);
+        assert!(matches!(
+            part1_policy(Surface::Error),
+            Err(AocError::Unsolvable { message, .. }) if message.starts_with("instruction 2 ")
+        ));
+        assert_eq!(Ok(45), part1_policy(Surface::Clamp));
+        assert_eq!(Ok(30), part1_policy(Surface::AllowNegative));
+
+        let part2_instructions =
+            parse_instructions("down 2\nup 5\nforward 2\ndown 10\nforward 1\n").unwrap();
+        let part2_policy = |surface| part2_with(part2_instructions.iter(), surface);
+        assert!(matches!(
+            part2_policy(Surface::Error),
+            Err(AocError::Unsolvable { message, .. }) if message.starts_with("instruction 3 ")
+        ));
+        assert_eq!(Ok(21), part2_policy(Surface::Clamp));
+        assert_eq!(Ok(3), part2_policy(Surface::AllowNegative));
+
+        let rising = parse_instructions("forward 2\nup 1\n").unwrap();
+        assert_eq!(
+            Err(AocError::unsolvable(
+                2,
+                "the submarine ended above the surface, at depth -1"
+            )),
+            part1_with(rising.iter(), Surface::AllowNegative)
+        );
+    }
+
+    #[test]
+    fn solves_from_reader() {
+        assert_eq!(Ok(150), part1_reader(EXAMPLE.as_bytes()));
+        assert_eq!(Ok(900), part2_reader(EXAMPLE.as_bytes()));
+        assert_eq!(
+            Err(AocError::parse(
+                2,
+                2,
+                1,
+                "unexpected instruction 'sideways'"
+            )),
+            part1_reader("forward 5\nsideways 5\n".as_bytes())
+        );
+    }
+
+    #[test]
+    fn traces_positions() {
+        let (_, events) = trace::capture(|| Day02.part2(EXAMPLE));
+        let positions = trace::named(&events, "position").collect::<Vec<_>>();
+        assert_eq!(6, positions.len());
+        assert_eq!("forward 8", positions[2]["instruction"]);
+        assert_eq!(
+            ("13", "40", "5"),
+            (
+                positions[2]["x"],
+                positions[2]["depth"],
+                positions[2]["aim"]
+            )
+        );
+    }
+
+    #[test]
+    fn reader_matches_str() {
+        assert_eq!(Day02.part1(INPUT), part1_reader(INPUT.as_bytes()));
+        assert_eq!(Day02.part2(INPUT), part2_reader(INPUT.as_bytes()));
+    }
 }
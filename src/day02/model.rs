@@ -0,0 +1,256 @@
+//! Ways of interpreting the submarine's instructions.
+//!
+//! Part 1 moves the submarine [directly](Direct), and part 2 [aims](Aimed)
+//! it. Other models, such as one with drag or a maximum depth, need only
+//! implement [`SubmarineModel`] to be followed with
+//! [`dive`](super::dive), and can be added to [`MODELS`] to be chosen by
+//! name with `dive --model`.
+
+use std::{convert::TryFrom, str::FromStr};
+
+use super::{Day02, Instruction};
+use crate::{
+    error::AocError,
+    solution::{Answer, Puzzle},
+};
+
+/// The state of a submarine, and how each instruction changes it.
+pub trait SubmarineModel: Default {
+    /// The state after one more instruction. New depths should be checked
+    /// with [`Surface::depth`].
+    fn step(&self, instruction: &Instruction, surface: Surface) -> Result<Self, Violation>;
+
+    fn position(&self) -> Position;
+
+    /// Any state besides the position, such as the aim, for tracing.
+    fn state(&self) -> Vec<(&'static str, String)> {
+        Vec::new()
+    }
+}
+
+/// How far forward and how deep the submarine is.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Position {
+    pub x: i64,
+    /// Negative above the surface, if the [`Surface`] policy allows it.
+    pub depth: i64,
+}
+impl Position {
+    /// The product of the distance and depth.
+    pub fn answer(&self) -> Result<Answer, AocError> {
+        let product = self
+            .x
+            .checked_mul(self.depth)
+            .ok_or_else(|| AocError::unsolvable(Day02::DAY, "the answer is too large to count"))?;
+        Answer::try_from(product).map_err(|_| {
+            AocError::unsolvable(
+                Day02::DAY,
+                format!(
+                    "the submarine ended above the surface, at depth {}",
+                    self.depth
+                ),
+            )
+        })
+    }
+}
+
+/// What to do when an instruction takes the submarine above the surface.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Surface {
+    /// Fail, naming the instruction, as the puzzle never rises above the surface.
+    #[default]
+    Error,
+    /// Stop at the surface, as a real submarine would.
+    Clamp,
+    /// Carry on with a negative depth. The answer is an error if the
+    /// submarine ends above the surface.
+    AllowNegative,
+}
+impl Surface {
+    /// A new depth, or `None` if it overflowed, as this policy says it should be.
+    pub fn depth(self, depth: Option<i64>) -> Result<i64, Violation> {
+        match depth.ok_or(Violation::Overflow)? {
+            depth if depth >= 0 => Ok(depth),
+            depth => match self {
+                Surface::Error => Err(Violation::AboveSurface),
+                Surface::Clamp => Ok(0),
+                Surface::AllowNegative => Ok(depth),
+            },
+        }
+    }
+}
+impl FromStr for Surface {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(Surface::Error),
+            "clamp" => Ok(Surface::Clamp),
+            "allow-negative" => Ok(Surface::AllowNegative),
+            _ => Err(format!(
+                "the surface policy must be error, clamp or allow-negative, but was '{}'",
+                s
+            )),
+        }
+    }
+}
+
+/// Why an instruction couldn't be followed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Violation {
+    AboveSurface,
+    Overflow,
+}
+
+/// Part 1: forward moves forward, and up and down change the depth.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Direct(Position);
+
+impl SubmarineModel for Direct {
+    fn step(&self, instruction: &Instruction, surface: Surface) -> Result<Self, Violation> {
+        let Position { x, depth } = self.0;
+        Ok(Self(match *instruction {
+            Instruction::Forward(f) => Position {
+                x: x.checked_add(f.into()).ok_or(Violation::Overflow)?,
+                depth,
+            },
+            Instruction::Down(d) => Position {
+                x,
+                depth: surface.depth(depth.checked_add(d.into()))?,
+            },
+            Instruction::Up(u) => Position {
+                x,
+                depth: surface.depth(depth.checked_sub(u.into()))?,
+            },
+        }))
+    }
+
+    fn position(&self) -> Position {
+        self.0
+    }
+}
+
+/// Part 2: up and down change the aim, and forward moves forward and sinks
+/// by the aim for each unit moved.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Aimed {
+    position: Position,
+    /// May be negative, pointing the submarine towards the surface.
+    aim: i64,
+}
+
+impl SubmarineModel for Aimed {
+    fn step(&self, instruction: &Instruction, surface: Surface) -> Result<Self, Violation> {
+        let Position { x, depth } = self.position;
+        Ok(match *instruction {
+            Instruction::Forward(f) => {
+                let f = i64::from(f);
+                let sink = self.aim.checked_mul(f).ok_or(Violation::Overflow)?;
+                Self {
+                    position: Position {
+                        x: x.checked_add(f).ok_or(Violation::Overflow)?,
+                        depth: surface.depth(depth.checked_add(sink))?,
+                    },
+                    ..*self
+                }
+            }
+            Instruction::Up(u) => Self {
+                aim: self.aim.checked_sub(u.into()).ok_or(Violation::Overflow)?,
+                ..*self
+            },
+            Instruction::Down(d) => Self {
+                aim: self.aim.checked_add(d.into()).ok_or(Violation::Overflow)?,
+                ..*self
+            },
+        })
+    }
+
+    fn position(&self) -> Position {
+        self.position
+    }
+
+    fn state(&self) -> Vec<(&'static str, String)> {
+        vec![("aim", self.aim.to_string())]
+    }
+}
+
+/// A model which can be chosen by name.
+pub struct Model {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Follows the instructions, returning where the submarine ends up.
+    pub dive: fn(&[Instruction], Surface) -> Result<Position, AocError>,
+}
+
+pub const MODELS: &[Model] = &[
+    Model {
+        name: "direct",
+        description: "up and down change the depth, as in part 1",
+        dive: dive_slice::<Direct>,
+    },
+    Model {
+        name: "aimed",
+        description: "up and down change the aim, as in part 2",
+        dive: dive_slice::<Aimed>,
+    },
+];
+
+/// The model with this name, if there is one.
+pub fn find(name: &str) -> Option<&'static Model> {
+    MODELS.iter().find(|m| m.name == name)
+}
+
+fn dive_slice<M: SubmarineModel>(
+    instructions: &[Instruction],
+    surface: Surface,
+) -> Result<Position, AocError> {
+    super::dive::<M>(instructions.iter(), surface)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day02::{dive, parse_instructions, EXAMPLE};
+
+    /// Each forward instruction moves one less than it says, as if held back
+    /// by the water.
+    #[derive(Default)]
+    struct Drag(Direct);
+    impl SubmarineModel for Drag {
+        fn step(&self, instruction: &Instruction, surface: Surface) -> Result<Self, Violation> {
+            let instruction = match *instruction {
+                Instruction::Forward(f) => Instruction::Forward(f.saturating_sub(1)),
+                other => other,
+            };
+            self.0.step(&instruction, surface).map(Drag)
+        }
+
+        fn position(&self) -> Position {
+            self.0.position()
+        }
+    }
+
+    #[test]
+    fn follows_any_model() {
+        let instructions = parse_instructions(EXAMPLE).unwrap();
+        let drag = dive::<Drag>(instructions.iter(), Surface::Error).unwrap();
+        assert_eq!(Position { x: 12, depth: 10 }, drag);
+        assert_eq!(Ok(120), drag.answer());
+    }
+
+    #[test]
+    fn finds_models_by_name() {
+        let instructions = parse_instructions(EXAMPLE).unwrap();
+        let answer = |name| (find(name).unwrap().dive)(&instructions, Surface::Error);
+        assert_eq!(Ok(Position { x: 15, depth: 10 }), answer("direct"));
+        assert_eq!(Ok(Position { x: 15, depth: 60 }), answer("aimed"));
+        assert!(find("drag").is_none());
+    }
+
+    #[test]
+    fn parses_surface_policy() {
+        assert_eq!(Ok(Surface::Clamp), "clamp".parse());
+        assert_eq!(Ok(Surface::AllowNegative), "allow-negative".parse());
+        assert!("ignore".parse::<Surface>().is_err());
+    }
+}
@@ -1,29 +1,135 @@
-pub fn part1(s: &'static str) -> usize {
-    let output = get_bit_counts(s);
+use std::io::BufRead;
+
+use crate::{
+    error::AocError,
+    generate::Rng,
+    solution::{Answer, Part, Puzzle},
+    stream::lines,
+    trace,
+};
+
+pub struct Day03;
+impl Puzzle for Day03 {
+    const DAY: u32 = 3;
+    const NAME: &'static str = "Binary Diagnostic";
+    const INPUT: &'static str = INPUT;
+
+    type Parsed<'a> = Vec<&'a str>;
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, AocError> {
+        let lines = input.lines().collect::<Vec<_>>();
+        let width = match lines.first() {
+            Some(first) => check_width(first.len())?,
+            None => return Err(no_numbers()),
+        };
+        for line in &lines {
+            check_line(input, line, width)?;
+        }
+        Ok(lines)
+    }
+    fn part1(lines: &Vec<&str>) -> Result<Answer, AocError> {
+        part1(lines)
+    }
+    fn part2(lines: &Vec<&str>) -> Result<Answer, AocError> {
+        part2(lines).map(Answer::from)
+    }
+    fn reference(lines: &Vec<&str>, part: Part) -> Result<Answer, AocError> {
+        reference(lines, part)
+    }
+}
+
+pub fn part1(lines: &[&str]) -> Result<Answer, AocError> {
+    let width = lines.first().ok_or_else(no_numbers)?.len();
+    let output = get_bit_counts(lines, width);
     let (gamma, epsilon) = get_rates(&output);
-    gamma * epsilon
+    Ok((gamma * epsilon) as Answer)
+}
+/// Solves part 1 a line at a time, keeping only a count for each bit.
+pub fn part1_reader(reader: impl BufRead) -> Result<Answer, AocError> {
+    let mut counts = Vec::new();
+    let mut count = 0;
+    for number in read_numbers(reader) {
+        let (value, width) = number?;
+        counts.resize(width, 0);
+        for (i, c) in counts.iter_mut().enumerate() {
+            *c += (value >> (width - 1 - i)) as usize & 1;
+        }
+        count += 1;
+    }
+    if count == 0 {
+        return Err(no_numbers());
+    }
+    let (gamma, epsilon) = get_rates(&majority(counts, count));
+    Ok((gamma * epsilon) as Answer)
 }
-pub fn part2(s: &'static str) -> u32 {
-    let length = s.lines().next().unwrap().len();
-    let mut values = s.lines().map(to_integer).collect::<Vec<_>>();
+/// Solves part 2 from a reader, keeping the numbers rather than their text.
+/// Unlike the other readers this holds the whole input, as each rating
+/// filters every number.
+pub fn part2_reader(reader: impl BufRead) -> Result<Answer, AocError> {
+    let mut values = Vec::new();
+    let mut length = 0;
+    for number in read_numbers(reader) {
+        let (value, width) = number?;
+        values.push(value);
+        length = width;
+    }
+    if values.is_empty() {
+        return Err(no_numbers());
+    }
+    get_rating(values, length).map(Answer::from)
+}
+
+pub fn part2(lines: &[&str]) -> Result<u32, AocError> {
+    let width = lines.first().ok_or_else(no_numbers)?.len();
+    let values = lines.iter().copied().map(to_integer).collect();
+    get_rating(values, width)
+}
+
+fn get_rating(mut values: Vec<u32>, length: usize) -> Result<u32, AocError> {
     values.sort_unstable();
 
     let (o2, co2) = (
         get_candidate(&values, length, true),
         get_candidate(&values, length, false),
     );
-    o2.expect("Failed to determine O2") * co2.expect("Failed to determine CO2")
+    match (o2, co2) {
+        (Some(o2), Some(co2)) => Ok(o2 * co2),
+        (None, _) => Err(AocError::unsolvable(
+            Day03::DAY,
+            "no single oxygen generator rating",
+        )),
+        (_, None) => Err(AocError::unsolvable(
+            Day03::DAY,
+            "no single CO2 scrubber rating",
+        )),
+    }
 }
 
 fn get_candidate(values: &[u32], length: usize, wants_majority: bool) -> Option<u32> {
     let mut partition = values;
     for i in (0..length).rev() {
-        partition = get_partition(partition, i, wants_majority);
-        if partition.len() == 1 {
-            return Some(partition[0]);
+        if let [only] = partition {
+            return Some(*only);
         }
+        partition = get_partition(partition, i, wants_majority);
+        trace::emit("candidates", || {
+            let binary =
+                |v: Option<&u32>| v.map_or(String::new(), |v| format!("{:01$b}", v, length));
+            vec![
+                (
+                    "rating",
+                    if wants_majority { "oxygen" } else { "co2" }.to_string(),
+                ),
+                ("bit", i.to_string()),
+                ("remaining", partition.len().to_string()),
+                ("lowest", binary(partition.first())),
+                ("highest", binary(partition.last())),
+            ]
+        });
+    }
+    match partition {
+        [only] => Some(*only),
+        _ => None,
     }
-    None
 }
 
 fn get_partition(t: &[u32], bit_index: usize, wants_majority: bool) -> &[u32] {
@@ -33,8 +139,8 @@ fn get_partition(t: &[u32], bit_index: usize, wants_majority: bool) -> &[u32] {
     // in which case the "high" half is the majority.
 
     let wants_top_half = match point * 2 {
-        l if l <= t.len() => wants_majority,
-        _ => !wants_majority,
+        l if l <= t.len() => !wants_majority,
+        _ => wants_majority,
     };
 
     // let point_is_low = point < t.len() / 2;
@@ -46,26 +152,80 @@ fn get_partition(t: &[u32], bit_index: usize, wants_majority: bool) -> &[u32] {
     }
 }
 
-fn get_bit_counts(s: &'static str) -> Vec<usize> {
-    let length = s.lines().next().unwrap().len();
-    let mut output = vec![0; length];
+fn get_bit_counts(lines: &[&str], width: usize) -> Vec<usize> {
+    let mut output = vec![0; width];
     let mut count = 0;
-    for line in input(s) {
+    for line in input(lines) {
         count += 1;
 
         for index in line {
             output[index] += 1;
         }
     }
+    majority(output, count)
+}
+
+/// Replaces each count of set bits with 1 if most of the `count` numbers had it set.
+fn majority(mut counts: Vec<usize>, count: usize) -> Vec<usize> {
     let threshold = count / 2;
-    for i in output.iter_mut() {
+    for i in counts.iter_mut() {
         *i = if *i > threshold { 1 } else { 0 }
     }
-    output
+    counts
 }
 pub const INPUT: &str = include_str!("day03/input.txt");
-fn input(s: &'static str) -> impl Iterator<Item = impl Iterator<Item = usize>> {
-    s.lines().map(iterate_string)
+pub const EXAMPLE: &str = include_str!("day03/example.txt");
+
+/// Reads one number from each line, with the number of bits it was written with.
+pub fn read_numbers(reader: impl BufRead) -> impl Iterator<Item = Result<(u32, usize), AocError>> {
+    let mut width = None;
+    lines(Day03::DAY, reader).map(move |line| {
+        let line = line?;
+        let expected = match width {
+            Some(w) => w,
+            None => *width.insert(check_width(line.text.len()).map_err(|e| line.locate(e))?),
+        };
+        check_line(&line.text, &line.text, expected).map_err(|e| line.locate(e))?;
+        Ok((to_integer(&line.text), expected))
+    })
+}
+
+fn no_numbers() -> AocError {
+    AocError::invalid(Day03::DAY, "no diagnostic numbers")
+}
+
+fn check_width(width: usize) -> Result<usize, AocError> {
+    if width == 0 || width > 32 {
+        return Err(AocError::invalid(
+            Day03::DAY,
+            format!("numbers must have 1 to 32 bits, but had {}", width),
+        ));
+    }
+    Ok(width)
+}
+
+/// Checks that `line`, which is part of `input`, is a binary number of `width` bits.
+fn check_line(input: &str, line: &str, width: usize) -> Result<(), AocError> {
+    if let Some(i) = line.find(|c| c != '0' && c != '1') {
+        return Err(AocError::at(
+            Day03::DAY,
+            input,
+            &line[i..],
+            "expected only '0' and '1'",
+        ));
+    }
+    if line.len() != width {
+        return Err(AocError::at(
+            Day03::DAY,
+            input,
+            line,
+            format!("expected {} bits, but found {}", width, line.len()),
+        ));
+    }
+    Ok(())
+}
+fn input<'a>(lines: &'a [&str]) -> impl Iterator<Item = impl Iterator<Item = usize> + 'a> {
+    lines.iter().copied().map(iterate_string)
 }
 
 fn iterate_string(s: &str) -> impl Iterator<Item = usize> + '_ {
@@ -75,7 +235,7 @@ fn iterate_string(s: &str) -> impl Iterator<Item = usize> + '_ {
         .filter_map(|(idx, c)| match c {
             b'0' => None,
             b'1' => Some(idx),
-            _ => panic!("Unexpected char in input: {}", c),
+            _ => unreachable!("validated by parse"),
         })
 }
 fn to_integer(s: &str) -> u32 {
@@ -84,7 +244,7 @@ fn to_integer(s: &str) -> u32 {
             | match cur {
                 b'0' => 0,
                 b'1' => 1,
-                _ => panic!("Unexpected char in input: {}", cur),
+                _ => unreachable!("validated by parse"),
             }
     })
 }
@@ -99,6 +259,71 @@ fn get_rates(bits: &[usize]) -> (usize, usize) {
     (big, small)
 }
 
+/// Writes `size` different numbers, at least 12 bits wide, from which both ratings can be found.
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let width = (12..=32)
+        .find(|&w| size as u64 <= 1 << w)
+        .expect("at most 2^32 numbers");
+    let mut values = Vec::with_capacity(size);
+    distinct(rng, size, width, 0, &mut values);
+    rng.shuffle(&mut values);
+    values
+        .iter()
+        .map(|v| format!("{:01$b}\n", v, width))
+        .collect()
+}
+
+/// Pushes `n` different values of `bits` bits, following `prefix`.
+///
+/// Any two values with a common prefix differ in the next bit, so a rating is
+/// found no matter which way the numbers are partitioned.
+fn distinct(rng: &mut Rng, n: usize, bits: usize, prefix: u32, values: &mut Vec<u32>) {
+    if n == 1 {
+        let low = rng.next_u64() as u32 & (((1u64 << bits) - 1) as u32);
+        values.push(((u64::from(prefix) << bits) as u32) | low);
+        return;
+    }
+    let half = 1 << (bits - 1);
+    let zeros = rng.range(n.saturating_sub(half).max(1) as u32..=half.min(n - 1) as u32) as usize;
+    distinct(rng, zeros, bits - 1, prefix << 1, values);
+    distinct(rng, n - zeros, bits - 1, (prefix << 1) | 1, values);
+}
+
+/// Counts each bit column by column, and filters the ratings as the puzzle describes.
+fn reference(lines: &[&str], part: Part) -> Result<Answer, AocError> {
+    let width = lines.first().ok_or_else(no_numbers)?.len();
+    let ones = |lines: &[&str], i: usize| lines.iter().filter(|l| l.as_bytes()[i] == b'1').count();
+    match part {
+        Part::One => {
+            let gamma = (0..width)
+                .map(|i| ones(lines, i) * 2 > lines.len())
+                .fold(0, |prev, bit| (prev << 1) | bit as Answer);
+            Ok(gamma * (!gamma & ((1 << width) - 1)))
+        }
+        Part::Two => {
+            let rating = |most_common: bool| {
+                let mut remaining = lines.to_vec();
+                for i in 0..width {
+                    if remaining.len() == 1 {
+                        break;
+                    }
+                    let ones = ones(&remaining, i);
+                    let keep_ones = (ones * 2 >= remaining.len()) == most_common;
+                    remaining.retain(|l| (l.as_bytes()[i] == b'1') == keep_ones);
+                }
+                match remaining[..] {
+                    [only] => Some(Answer::from(to_integer(only))),
+                    _ => None,
+                }
+            };
+            match (rating(true), rating(false)) {
+                (Some(o2), Some(co2)) => Ok(o2 * co2),
+                _ => Err(AocError::unsolvable(Day03::DAY, "no single rating")),
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -130,22 +355,12 @@ mod tests {
         assert_eq!((0b000111, 0b111000), get_rates(&[0, 0, 0, 1, 1, 1]));
     }
 
-    const TEST_INPUT: &str = r#"00100
-11110
-10110
-10111
-10101
-01111
-00111
-11100
-10000
-11001
-00010
-01010"#;
-
     #[test]
     fn gets_test_bit_counts() {
-        assert_eq!(vec![1, 0, 1, 1, 0], get_bit_counts(TEST_INPUT));
+        assert_eq!(
+            vec![1, 0, 1, 1, 0],
+            get_bit_counts(&Day03::parse(EXAMPLE).unwrap(), 5)
+        );
     }
     #[test]
     fn gets_test_part1() {
@@ -168,6 +383,62 @@ mod tests {
 
     #[test]
     fn gets_test_part2() {
-        assert_eq!(230, part2(TEST_INPUT))
+        assert_eq!(Ok(230), part2(&Day03::parse(EXAMPLE).unwrap()))
+    }
+
+    #[test]
+    fn traces_candidates() {
+        let (_, events) = trace::capture(|| part2(&Day03::parse(EXAMPLE).unwrap()));
+        let oxygen = trace::named(&events, "candidates")
+            .filter(|c| c["rating"] == "oxygen")
+            .map(|c| c["remaining"].parse().unwrap())
+            .collect::<Vec<usize>>();
+        // as in the puzzle description.
+        assert_eq!(vec![7, 4, 3, 2, 1], oxygen);
+    }
+
+    #[test]
+    fn rejects_bad_diagnostics() {
+        assert!(matches!(
+            Day03::parse("0101\n0121"),
+            Err(AocError::Parse {
+                line: 2,
+                column: 3,
+                ..
+            })
+        ));
+        assert!(matches!(
+            Day03::parse("0101\n011"),
+            Err(AocError::Parse { line: 2, .. })
+        ));
+        assert!(matches!(
+            Day03::parse(""),
+            Err(AocError::InvalidInput { .. })
+        ));
+        assert_eq!(Err(no_numbers()), part1(&[]));
+        assert_eq!(Err(no_numbers()), part2(&[]));
+        assert_eq!(Err(no_numbers()), reference(&[], Part::Two));
+    }
+
+    #[test]
+    fn solves_from_reader() {
+        use crate::solution::Solution;
+
+        assert_eq!(Ok(198), part1_reader(EXAMPLE.as_bytes()));
+        assert_eq!(Ok(230), part2_reader(EXAMPLE.as_bytes()));
+        assert_eq!(Day03.part1(INPUT), part1_reader(INPUT.as_bytes()));
+        assert_eq!(Day03.part2(INPUT), part2_reader(INPUT.as_bytes()));
+        assert!(matches!(
+            part1_reader("0101\n0121".as_bytes()),
+            Err(AocError::Parse {
+                line: 2,
+                column: 3,
+                ..
+            })
+        ));
+        assert!(matches!(
+            part2_reader("".as_bytes()),
+            Err(AocError::InvalidInput { .. })
+        ));
     }
 }
@@ -1,37 +1,73 @@
 use std::collections::HashSet;
 
+use crate::{
+    error::{locate, parse_number, AocError},
+    generate::Rng,
+    solution::{Answer, Part, Puzzle},
+    trace,
+};
+
 const SIZE: usize = 5;
 pub const INPUT: &str = include_str!("day04/input.txt");
+pub const EXAMPLE: &str = include_str!("day04/example.txt");
 
-pub fn part1(input: &str) -> usize {
-    let (numbers, mut boards) = parse_input(input).unwrap();
-    play(&numbers, &mut boards).expect("No board won")
-}
+pub struct Day04;
+impl Puzzle for Day04 {
+    const DAY: u32 = 4;
+    const NAME: &'static str = "Giant Squid";
+    const INPUT: &'static str = INPUT;
 
-pub fn part2(input: &str) -> usize {
-    let (numbers, boards) = parse_input(input).unwrap();
-    play_last(&numbers, boards).expect("No single unique board lost")
+    type Parsed<'a> = (Vec<u8>, Vec<Board>);
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, AocError> {
+        parse_input(input)
+    }
+    fn part1((numbers, boards): &(Vec<u8>, Vec<Board>)) -> Result<Answer, AocError> {
+        let mut boards = boards.clone();
+        play(numbers, &mut boards)
+            .map(|score| score as Answer)
+            .ok_or_else(|| AocError::unsolvable(Self::DAY, "no board won"))
+    }
+    fn part2((numbers, boards): &(Vec<u8>, Vec<Board>)) -> Result<Answer, AocError> {
+        play_last(numbers, boards.clone())
+            .map(|score| score as Answer)
+            .ok_or_else(|| AocError::unsolvable(Self::DAY, "no single unique board lost"))
+    }
+    fn reference(parsed: &(Vec<u8>, Vec<Board>), part: Part) -> Result<Answer, AocError> {
+        reference(parsed, part)
+    }
 }
 
-fn play(numbers: &[u8], boards: &mut [Board]) -> Option<usize> {
+/// Plays bingo until a board wins, returning its final score.
+pub fn play(numbers: &[u8], boards: &mut [Board]) -> Option<usize> {
     let mut h = HashSet::new();
     for &n in numbers {
         h.insert(n);
-        for b in boards.iter_mut() {
-            if b.mark(n) {
-                return Some(b.score(h) * (n as usize));
+        trace_call(n, boards);
+        for (i, b) in boards.iter_mut().enumerate() {
+            let won = b.mark(n);
+            trace_mark(i, b, n);
+            if won {
+                let score = b.score(h) * (n as usize);
+                trace_win(i, score);
+                return Some(score);
             }
         }
     }
     None
 }
-fn play_last(numbers: &[u8], mut boards: Vec<Board>) -> Option<usize> {
+/// Plays bingo until only one board is left to win, returning its final score.
+pub fn play_last(numbers: &[u8], mut boards: Vec<Board>) -> Option<usize> {
     let mut h = HashSet::new();
     for &n in numbers {
         h.insert(n);
+        trace_call(n, &boards);
         if let [last] = boards.as_mut_slice() {
-            if last.mark(n) {
-                return Some(last.score(h) * (n as usize));
+            let won = last.mark(n);
+            trace_mark(0, last, n);
+            if won {
+                let score = last.score(h) * (n as usize);
+                trace_win(0, score);
+                return Some(score);
             }
         } else if boards.is_empty() {
             return None;
@@ -39,34 +75,101 @@ fn play_last(numbers: &[u8], mut boards: Vec<Board>) -> Option<usize> {
             let finished = boards
                 .iter_mut()
                 .enumerate()
-                .filter_map(|(idx, b)| if b.mark(n) { Some(idx) } else { None })
+                .filter_map(|(idx, b)| {
+                    let won = b.mark(n);
+                    trace_mark(idx, b, n);
+                    won.then_some(idx)
+                })
                 .collect::<Vec<_>>();
             for &idx in finished.iter().rev() {
                 boards.remove(idx);
             }
+            if !finished.is_empty() {
+                trace::emit("won", || vec![("boards", finished.len().to_string())]);
+            }
         }
     }
-    unreachable!()
+    None
+}
+
+/// Traces a called number, with how many of the boards still playing have it.
+fn trace_call(number: u8, boards: &[Board]) {
+    trace::emit("call", || {
+        let marked = boards.iter().filter(|b| b.numbers.contains(&number));
+        vec![
+            ("number", number.to_string()),
+            ("marked", marked.count().to_string()),
+            ("playing", boards.len().to_string()),
+        ]
+    });
 }
 
-fn parse_input(input: &str) -> Result<(Vec<u8>, Vec<Board>), String> {
-    let (first_line, input) =
-        one_line(input).ok_or_else(|| "Did not start with the call numbers".to_string())?;
+/// Traces a board which has the called number, by its index among the boards
+/// still playing: where the number is, counting from 1, and how many numbers
+/// are now marked in each row and column.
+fn trace_mark(board: usize, marked: &Board, number: u8) {
+    if !trace::enabled() {
+        return;
+    }
+    let index = match marked.numbers.iter().position(|&i| i == number) {
+        Some(index) => index,
+        None => return,
+    };
+    trace::emit("mark", || {
+        let counts = |scores: &[u8]| {
+            let counts = scores.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+            counts.join(",")
+        };
+        vec![
+            ("board", board.to_string()),
+            ("number", number.to_string()),
+            ("row", (index / SIZE + 1).to_string()),
+            ("column", (index % SIZE + 1).to_string()),
+            ("rows", counts(&marked.row_score)),
+            ("columns", counts(&marked.col_score)),
+        ]
+    });
+}
+
+/// Traces the winning board, by its index among the boards still playing.
+fn trace_win(board: usize, score: usize) {
+    trace::emit("win", || {
+        vec![("board", board.to_string()), ("score", score.to_string())]
+    });
+}
+
+/// Parses the called numbers, followed by the boards.
+pub fn parse_input(whole: &str) -> Result<(Vec<u8>, Vec<Board>), AocError> {
+    let (first_line, input) = one_line(whole).ok_or_else(|| {
+        AocError::at(
+            Day04::DAY,
+            whole,
+            &whole[whole.len()..],
+            "expected the call numbers followed by a line break",
+        )
+    })?;
     let numbers = first_line
         .split(',')
-        .map(|t| t.parse::<u8>())
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| e.to_string())?;
+        .map(|t| parse_number(Day04::DAY, whole, t))
+        .collect::<Result<Vec<_>, _>>()?;
     let (_, input) = match one_line(input) {
         Some((line, rest)) if line.is_empty() => Ok((line, rest)),
-        _ => Err("Call numbers not followed by an empty line".to_string()),
+        _ => Err(AocError::at(
+            Day04::DAY,
+            whole,
+            input,
+            "call numbers not followed by an empty line",
+        )),
     }?;
-    let first = Board::consume(input)?.ok_or("No boards")?;
+    // boards report errors relative to their own first line.
+    let consume =
+        |input| Board::consume(input).map_err(|e| e.offset_lines(locate(whole, input).0 - 1));
+    let first = consume(input)?.ok_or_else(|| AocError::invalid(Day04::DAY, "no boards"))?;
     let boards = std::iter::successors(Some(Ok(first)), |prev| {
         // I'm sure there's a more elegant way to do this, but Clippy is not complaining,
         // so ?
         if let Ok((_, input)) = prev {
-            let result = Board::consume(input);
+            let result = consume(input);
             match result {
                 Err(e) => Some(Err(e)),
                 Ok(None) => None,
@@ -88,7 +191,8 @@ fn one_line(input: &str) -> Option<(&str, &str)> {
     Some((line, rest))
 }
 
-struct Board {
+#[derive(Clone)]
+pub struct Board {
     numbers: [u8; SIZE * SIZE],
     row_score: [u8; SIZE],
     col_score: [u8; SIZE],
@@ -96,7 +200,7 @@ struct Board {
 impl Board {
     /// Marks the number, if present on the board.
     /// Returns `true` if the board has now won.
-    fn mark(&mut self, number: u8) -> bool {
+    pub fn mark(&mut self, number: u8) -> bool {
         if let Some(index) = self.numbers.iter().position(|i| i == &number) {
             let col = index % SIZE;
             let row = index / SIZE;
@@ -113,7 +217,7 @@ impl Board {
 
     /// Calculates the (puzzle-defined) board "score" being the sum of all
     /// unmarked numbers. Does not validate if the board is already won.
-    fn score(&self, numbers: HashSet<u8>) -> usize {
+    pub fn score(&self, numbers: HashSet<u8>) -> usize {
         self.numbers
             .iter()
             .filter(|&i| !numbers.contains(i))
@@ -123,37 +227,54 @@ impl Board {
 
     /// Produces a board by consuming some part of the string, or `None`
     /// if the input is exhausted.
-    fn consume(input: &str) -> Result<Option<(Self, &str)>, String> {
+    pub fn consume(input: &str) -> Result<Option<(Self, &str)>, AocError> {
         if input.is_empty() {
             Ok(None)
         } else {
             let mut arr = [0u8; SIZE * SIZE];
+            let board = input;
             let mut input = input;
             for i in 0..SIZE {
                 let (line, rest) = one_line(input).unwrap_or((input, ""));
                 let part = &mut arr[i * SIZE..][..SIZE];
                 let mut j = 0;
                 for token in line.split_ascii_whitespace() {
-                    // why do I need to add this error type annotation?
-                    // rust-analyzer didn't need it.
-                    let result: u8 = token
-                        .parse()
-                        .map_err(|op: std::num::ParseIntError| op.to_string())?;
-                    part[j] = result;
+                    if j == SIZE {
+                        return Err(AocError::at(
+                            Day04::DAY,
+                            board,
+                            token,
+                            format!("board rows must have {} numbers", SIZE),
+                        ));
+                    }
+                    part[j] = parse_number(Day04::DAY, board, token)?;
                     j += 1;
                 }
                 if j != SIZE {
-                    return Err(format!("line {} of the board only has {} numbers", i, j));
+                    return Err(AocError::at(
+                        Day04::DAY,
+                        board,
+                        line,
+                        format!("board row only has {} numbers", j),
+                    ));
                 }
                 input = rest;
             }
 
+            let not_terminated = |at| {
+                AocError::at(
+                    Day04::DAY,
+                    board,
+                    at,
+                    "board did not terminate with an empty line",
+                )
+            };
             let (blank, rest) = if input.is_empty() {
                 (input, input)
             } else {
                 input
                     .split_once('\n')
-                    .ok_or_else(|| "Board did not terminate with an empty line".to_string())?
+                    .ok_or_else(|| not_terminated(input))?
             };
             if blank.trim().is_empty() {
                 let output = Self {
@@ -163,12 +284,117 @@ impl Board {
                 };
                 Ok(Some((output, rest)))
             } else {
-                Err("Board did not terminate with an empty line".to_string())
+                Err(not_terminated(blank))
             }
         }
     }
 }
 
+/// Writes every number from 0 to 99 in a random order, followed by `size` boards
+/// of which exactly one wins last.
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let mut numbers = (0..100u8).collect::<Vec<_>>();
+    rng.shuffle(&mut numbers);
+    let mut called_at = [0; 100];
+    for (i, &n) in numbers.iter().enumerate() {
+        called_at[n as usize] = i;
+    }
+    let mut random_board = || {
+        let mut board = (0..100u8).collect::<Vec<_>>();
+        rng.shuffle(&mut board);
+        board.truncate(SIZE * SIZE);
+        board
+    };
+    let wins_at = |board: &[u8]| {
+        let last_call = |cells: &mut dyn Iterator<Item = &u8>| {
+            cells.map(|&n| called_at[n as usize]).max().unwrap()
+        };
+        (0..SIZE)
+            .flat_map(|i| {
+                [
+                    last_call(&mut board[i * SIZE..][..SIZE].iter()),
+                    last_call(&mut board[i..].iter().step_by(SIZE)),
+                ]
+            })
+            .min()
+            .unwrap()
+    };
+    let mut boards = (0..size).map(|_| random_board()).collect::<Vec<_>>();
+    // the last board to win must win alone, so replace any which tie with it.
+    loop {
+        let last = boards.iter().map(|b| wins_at(b)).max().unwrap_or(0);
+        let tied = boards
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| wins_at(b) == last)
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        match tied[..] {
+            [] | [_] => break,
+            [.., i] => boards[i] = random_board(),
+        }
+    }
+
+    let mut output = numbers
+        .iter()
+        .map(|n| n.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    output.push('\n');
+    for board in boards {
+        output.push('\n');
+        for row in board.chunks(SIZE) {
+            let row = row.iter().map(|n| format!("{:>2}", n)).collect::<Vec<_>>();
+            output.push_str(&row.join(" "));
+            output.push('\n');
+        }
+    }
+    output
+}
+
+/// Plays each board on its own, checking every row and column after every call.
+fn reference((numbers, boards): &(Vec<u8>, Vec<Board>), part: Part) -> Result<Answer, AocError> {
+    let wins = boards.iter().map(|b| {
+        let mut marked = [false; SIZE * SIZE];
+        for (turn, &n) in numbers.iter().enumerate() {
+            for (m, &value) in marked.iter_mut().zip(b.numbers.iter()) {
+                *m |= value == n;
+            }
+            let row = (0..SIZE).any(|r| (0..SIZE).all(|c| marked[r * SIZE + c]));
+            let col = (0..SIZE).any(|c| (0..SIZE).all(|r| marked[r * SIZE + c]));
+            if row || col {
+                let unmarked = b
+                    .numbers
+                    .iter()
+                    .zip(marked.iter())
+                    .filter(|(_, &m)| !m)
+                    .map(|(&v, _)| Answer::from(v))
+                    .sum::<Answer>();
+                return Some((turn, unmarked * Answer::from(n)));
+            }
+        }
+        None
+    });
+    let wins = wins.collect::<Option<Vec<_>>>();
+    let chosen = match part {
+        Part::One => wins.and_then(|w| w.into_iter().min_by_key(|&(turn, _)| turn)),
+        Part::Two => wins.and_then(|w| {
+            let last = w.iter().map(|&(turn, _)| turn).max()?;
+            match w
+                .iter()
+                .filter(|&&(turn, _)| turn == last)
+                .collect::<Vec<_>>()[..]
+            {
+                [&only] => Some(only),
+                _ => None,
+            }
+        }),
+    };
+    chosen
+        .map(|(_, score)| score)
+        .ok_or_else(|| AocError::unsolvable(Day04::DAY, "no board won alone"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -188,6 +414,32 @@ mod tests {
         assert_eq!(None, smaller('_'));
     }
 
+    #[test]
+    fn traces_calls() {
+        let (numbers, mut boards) = parse_input(EXAMPLE).unwrap();
+        let (_, events) = trace::capture(|| play(&numbers, &mut boards));
+        let calls = trace::named(&events, "call").collect::<Vec<_>>();
+        // as in the puzzle description, the third board wins on the twelfth call.
+        assert_eq!(12, calls.len());
+        assert_eq!(("24", "3"), (calls[11]["number"], calls[11]["marked"]));
+        let marks = trace::named(&events, "mark").collect::<Vec<_>>();
+        // 7 is on every board, so marks the first of each.
+        let sevens = marks.iter().filter(|m| m["number"] == "7");
+        assert_eq!(
+            vec!["0", "1", "2"],
+            sevens.map(|m| m["board"]).collect::<Vec<_>>()
+        );
+        // the winning mark completes the third board's top row.
+        let last = marks.last().unwrap();
+        assert_eq!(
+            ("2", "24", "1", "4"),
+            (last["board"], last["number"], last["row"], last["column"])
+        );
+        assert_eq!(("5,1,1,2,3", "2,3,2,2,3"), (last["rows"], last["columns"]));
+        let win = trace::named(&events, "win").next().unwrap();
+        assert_eq!(("2", "4512"), (win["board"], win["score"]));
+    }
+
     #[test]
     fn marks_board() {
         let mut board = Board {
@@ -199,7 +451,7 @@ mod tests {
             row_score: [0; 5],
         };
         for i in 0..(SIZE as u8) - 1 {
-            assert_eq!(false, board.mark(i));
+            assert!(!board.mark(i));
         }
         assert!(board.mark(SIZE as u8 - 1));
         assert_eq!([SIZE as u8, 0, 0, 0, 0], board.row_score);
@@ -209,7 +461,7 @@ mod tests {
     }
 
     #[test]
-    fn parses_board() -> Result<(), String> {
+    fn parses_board() -> Result<(), AocError> {
         const BOARD_TEXT: &str = r#"46 53 14 17 75
 71  4 70 99 48
 65 96 68 80 72
@@ -218,7 +470,7 @@ mod tests {
 
 rest"#;
         let (Board { numbers, .. }, rest) =
-            Board::consume(BOARD_TEXT)?.ok_or("Consume unexpectedly returned None")?;
+            Board::consume(BOARD_TEXT)?.expect("Consume unexpectedly returned None");
         assert_eq!("rest", rest);
         assert_eq!(
             [
@@ -230,7 +482,7 @@ rest"#;
         Ok(())
     }
     #[test]
-    fn parses_no_board() -> Result<(), String> {
+    fn parses_no_board() -> Result<(), AocError> {
         let result = Board::consume("")?;
         assert!(result.is_none());
         Ok(())
@@ -242,37 +494,31 @@ rest"#;
         assert!(result.is_err())
     }
 
-    const TEST_INPUT: &str = r#"7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1
-
-22 13 17 11  0
- 8  2 23  4 24
-21  9 14 16  7
- 6 10  3 18  5
- 1 12 20 15 19
-
- 3 15  0  2 22
- 9 18 13 17  5
-19  8  7 25 23
-20 11 10 24  4
-14 21 16 12  6
+    #[test]
+    fn locates_board_errors() {
+        let input = EXAMPLE.replacen("24  4", "24  x", 1);
+        assert!(matches!(
+            parse_input(&input),
+            Err(AocError::Parse {
+                line: 12,
+                column: 14,
+                ..
+            })
+        ));
+    }
 
-14 21 17 24  4
-10 16 15  9 19
-18  8 23 26 20
-22 11 13  6  5
- 2  0 12  3  7"#;
     #[test]
-    fn parses_test_input() -> Result<(), String> {
-        let (numbers, mut boards) = parse_input(TEST_INPUT)?;
-        let score = play(&numbers, &mut boards).ok_or("No board won".to_string())?;
+    fn parses_test_input() -> Result<(), AocError> {
+        let (numbers, mut boards) = parse_input(EXAMPLE)?;
+        let score = play(&numbers, &mut boards).expect("No board won");
         assert_eq!(4512, score);
         Ok(())
     }
 
     #[test]
-    fn runs_part_two() -> Result<(), String> {
-        let (numbers, boards) = parse_input(TEST_INPUT)?;
-        let score = play_last(&numbers, boards).ok_or("No single unique board lost".to_string())?;
+    fn runs_part_two() -> Result<(), AocError> {
+        let (numbers, boards) = parse_input(EXAMPLE)?;
+        let score = play_last(&numbers, boards).expect("No single unique board lost");
         assert_eq!(1924, score);
         Ok(())
     }
@@ -3,30 +3,78 @@ mod diagonal;
 use std::{
     cmp::Ordering,
     collections::{HashMap, HashSet},
+    io::BufRead,
     ops::RangeInclusive,
 };
 
 use nom::{
     bytes::complete::tag,
-    character::complete::{char, line_ending, u32},
-    combinator::opt,
-    multi::separated_list0,
-    sequence::{separated_pair, terminated},
+    character::complete::{char, u32},
+    combinator::all_consuming,
+    sequence::separated_pair,
     IResult,
 };
 
 use self::diagonal::Diagonal;
+use crate::{
+    error::AocError,
+    generate::Rng,
+    solution::{Answer, Part, Puzzle},
+    stream::lines,
+    trace,
+};
 
 pub const INPUT: &str = include_str!("day05/input.txt");
-pub fn part1(input: &str) -> usize {
-    let lines = Line::parse_all(input).unwrap();
-    let intersections = do_set_things(&lines, false);
-    intersections.len()
+pub const EXAMPLE: &str = include_str!("day05/example.txt");
+pub struct Day05;
+impl Puzzle for Day05 {
+    const DAY: u32 = 5;
+    const NAME: &'static str = "Hydrothermal Venture";
+    const INPUT: &'static str = INPUT;
+    const VERSION: u32 = 2;
+
+    type Parsed<'a> = Vec<Line>;
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, AocError> {
+        Line::parse_all(input)
+    }
+    fn part1(lines: &Vec<Line>) -> Result<Answer, AocError> {
+        let intersections = do_set_things(lines, false);
+        Ok(intersections.len() as Answer)
+    }
+    fn part2(lines: &Vec<Line>) -> Result<Answer, AocError> {
+        let cells = do_map_things(lines);
+        if trace::enabled() {
+            for (y, row) in diagram(&cells).into_iter().enumerate() {
+                trace::emit("row", || vec![("y", y.to_string()), ("cells", row)]);
+            }
+        }
+        Ok(cells.values().filter(|&&v| v > 1).count() as Answer)
+    }
+    fn reference(lines: &Vec<Line>, part: Part) -> Result<Answer, AocError> {
+        reference(lines, part)
+    }
+}
+
+/// Solves part 1 from a reader, keeping only the count for each point covered.
+pub fn part1_reader(reader: impl BufRead) -> Result<Answer, AocError> {
+    count_overlaps(reader, false)
 }
-pub fn part2(input: &str) -> usize {
-    let lines = Line::parse_all(input).unwrap();
-    let cells = do_map_things(&lines);
-    cells.values().filter(|&&v| v > 1).count()
+/// Solves part 2 from a reader, keeping only the count for each point covered.
+pub fn part2_reader(reader: impl BufRead) -> Result<Answer, AocError> {
+    count_overlaps(reader, true)
+}
+
+/// Counts the points covered by more than one line, adding each line to the
+/// counts as it is read.
+fn count_overlaps(reader: impl BufRead, diagonals: bool) -> Result<Answer, AocError> {
+    let mut cells = HashMap::new();
+    for line in Line::read_all(reader) {
+        let line = line?;
+        if diagonals || !matches!(line.direction(), Ok(Segment::Diagonal(_))) {
+            add_cells(&mut cells, &line);
+        }
+    }
+    Ok(cells.values().filter(|&&v| v > 1).count() as Answer)
 }
 
 fn make_partitioins(lines: &[Line]) -> (Vec<Rectilinear>, Vec<Rectilinear>, Vec<Diagonal>) {
@@ -34,7 +82,7 @@ fn make_partitioins(lines: &[Line]) -> (Vec<Rectilinear>, Vec<Rectilinear>, Vec<
     let mut y = Vec::new();
     let mut d = Vec::new();
     for l in lines {
-        match l.direction().unwrap() {
+        match l.direction().expect("validated by parse") {
             Segment::Rectilinear(r) => {
                 if r.0 == Direction::X {
                     x.push(r);
@@ -53,14 +101,14 @@ fn make_partitioins(lines: &[Line]) -> (Vec<Rectilinear>, Vec<Rectilinear>, Vec<
     fn order_by_major(u: &Rectilinear, v: &Rectilinear) -> Ordering {
         let outer = u.1.cmp(&v.1);
         match outer {
-            Ordering::Equal => u.2.start().cmp(&v.2.start()),
+            Ordering::Equal => u.2.start().cmp(v.2.start()),
             _ => outer,
         }
     }
 }
 
 fn get_self_overlaps<'a, F, G>(
-    items: &'a Vec<Rectilinear>,
+    items: &'a [Rectilinear],
     m: F,
 ) -> impl Iterator<Item = (u32, u32)> + 'a
 where
@@ -90,17 +138,42 @@ fn get_subrange<'a>(items: &'a [Rectilinear], range: &'a RangeInclusive<u32>) ->
 fn do_map_things(lines: &[Line]) -> HashMap<(u32, u32), usize> {
     let mut cells = HashMap::new();
     for l in lines {
-        for p in l.direction().unwrap().points() {
-            if let Some(r) = cells.get_mut(&p) {
-                *r = *r + 1;
-            } else {
-                cells.insert(p, 1usize);
-            }
-        }
+        add_cells(&mut cells, l);
     }
     cells
 }
 
+/// Counts one more line covering each of the line's points.
+fn add_cells(cells: &mut HashMap<(u32, u32), usize>, line: &Line) {
+    for p in line.direction().expect("validated by parse").points() {
+        *cells.entry(p).or_insert(0) += 1;
+    }
+}
+
+/// The rows of the diagram drawn in the puzzle description, with the number of
+/// lines covering each point, or none if the grid is too large to read.
+fn diagram(cells: &HashMap<(u32, u32), usize>) -> Vec<String> {
+    // a point at u32::MAX has no room for the grid, let alone the screen.
+    let size = cells.keys().try_fold((0, 0), |(w, h), &(x, y)| {
+        Some((w.max(x.checked_add(1)?), h.max(y.checked_add(1)?)))
+    });
+    let (width, height) = match size {
+        Some((width, height)) if width <= 100 && height <= 100 => (width, height),
+        _ => return Vec::new(),
+    };
+    (0..height)
+        .map(|y| {
+            (0..width)
+                .map(|x| match cells.get(&(x, y)) {
+                    None => '.',
+                    Some(&count) if count > 9 => '*',
+                    Some(&count) => char::from_digit(count as u32, 10).unwrap(),
+                })
+                .collect()
+        })
+        .collect()
+}
+
 fn do_set_things(lines: &[Line], consider_diagonal: bool) -> HashSet<(u32, u32)> {
     let (x, y, d) = make_partitioins(lines);
     let overlaps_x = get_self_overlaps(&x, |r| {
@@ -153,10 +226,14 @@ fn do_set_things(lines: &[Line], consider_diagonal: bool) -> HashSet<(u32, u32)>
     intersections
 }
 
+/// The largest coordinate, small enough that the sum of two fits in an `i32`,
+/// as the diagonal intersections need.
+pub const MAX_COORDINATE: u32 = (1 << 30) - 1;
+
 #[derive(Debug, PartialEq, Clone, Copy)]
-struct Point(u32, u32);
+pub struct Point(pub u32, pub u32);
 #[derive(Debug, PartialEq, Clone, Copy)]
-struct Line(Point, Point);
+pub struct Line(pub Point, pub Point);
 impl Point {
     fn parse(input: &str) -> IResult<&str, Self> {
         let (rest, (u, v)) = separated_pair(u32, char(','), u32)(input)?;
@@ -168,15 +245,41 @@ impl Line {
         let (rest, (l, r)) = separated_pair(Point::parse, tag(" -> "), Point::parse)(input)?;
         Ok((rest, Self(l, r)))
     }
-    fn parse_all(input: &str) -> Result<Vec<Self>, String> {
-        let (rest, vec) =
-            terminated(separated_list0(line_ending, Self::parse), opt(line_ending))(input)
-                .map_err(|e| e.to_string())?;
-        if rest.is_empty() {
-            Ok(vec)
-        } else {
-            Err("Did not consume input".to_string())
+    /// Parses one line segment per line, checking that each is rectilinear or diagonal.
+    pub fn parse_all(input: &str) -> Result<Vec<Self>, AocError> {
+        Self::read_all(input.as_bytes()).collect()
+    }
+    /// Reads one line segment per line, as [`Line::parse_all`] does.
+    pub fn read_all(reader: impl BufRead) -> impl Iterator<Item = Result<Self, AocError>> {
+        lines(Day05::DAY, reader).map(|line| {
+            let line = line?;
+            Self::parse_checked(&line.text).map_err(|e| line.locate(e))
+        })
+    }
+    fn parse_checked(text: &str) -> Result<Self, AocError> {
+        let (_, line) =
+            all_consuming(Self::parse)(text).map_err(|e: nom::Err<nom::error::Error<&str>>| {
+                let rest = match &e {
+                    nom::Err::Error(e) | nom::Err::Failure(e) => e.input,
+                    nom::Err::Incomplete(_) => &text[text.len()..],
+                };
+                AocError::at(Day05::DAY, text, rest, "expected 'x1,y1 -> x2,y2'")
+            })?;
+        let numbers = text.split(|c: char| !c.is_ascii_digit());
+        if let Some(large) = numbers
+            .filter(|n| !n.is_empty())
+            .find(|n| n.parse::<u32>().map_or(true, |n| n > MAX_COORDINATE))
+        {
+            return Err(AocError::at(
+                Day05::DAY,
+                text,
+                large,
+                format!("coordinates must be at most {}", MAX_COORDINATE),
+            ));
         }
+        line.direction()
+            .map_err(|message| AocError::at(Day05::DAY, text, text, message))?;
+        Ok(line)
     }
     fn direction(&self) -> Result<Segment, String> {
         match *self {
@@ -189,6 +292,7 @@ impl Line {
             Line(Point(lx, ly), Point(rx, ry)) => {
                 // doubtless there is a more efficient way to do this,
                 // but it is too tedious.
+                // coordinates are at most MAX_COORDINATE, so fit in an i32.
                 let dx = (rx as i32) - (lx as i32);
                 let dy = (ry as i32) - (ly as i32);
                 if dx.abs() != dy.abs() {
@@ -242,21 +346,55 @@ enum Direction {
     X,
     Y,
 }
+/// Writes `size` horizontal, vertical or diagonal line segments on a 1000 by 1000 grid.
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    const EDGE: u32 = 999;
+    let mut output = String::new();
+    let mut written = 0;
+    while written < size {
+        let (x, y) = (rng.range(0..=EDGE), rng.range(0..=EDGE));
+        let (dx, dy) = (rng.range(0..=2) as i64 - 1, rng.range(0..=2) as i64 - 1);
+        let reach = |p: u32, d: i64| match d {
+            1 => EDGE - p,
+            -1 => p,
+            _ => EDGE,
+        };
+        let longest = reach(x, dx).min(reach(y, dy)).min(300);
+        if (dx, dy) == (0, 0) || longest == 0 {
+            continue;
+        }
+        let length = i64::from(rng.range(1..=longest));
+        let end = |p: u32, d: i64| (i64::from(p) + d * length) as u32;
+        output.push_str(&format!("{},{} -> {},{}\n", x, y, end(x, dx), end(y, dy)));
+        written += 1;
+    }
+    output
+}
+
+/// Counts every point of every line in a map.
+fn reference(lines: &[Line], part: Part) -> Result<Answer, AocError> {
+    let mut counts = HashMap::new();
+    for &Line(Point(x1, y1), Point(x2, y2)) in lines {
+        if part == Part::One && x1 != x2 && y1 != y2 {
+            continue;
+        }
+        let (dx, dy) = (i64::from(x2) - i64::from(x1), i64::from(y2) - i64::from(y1));
+        for i in 0..=dx.abs().max(dy.abs()) {
+            let point = (
+                i64::from(x1) + dx.signum() * i,
+                i64::from(y1) + dy.signum() * i,
+            );
+            *counts.entry(point).or_insert(0) += 1;
+        }
+    }
+    Ok(counts.values().filter(|&&c| c > 1).count() as Answer)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::solution::Solution;
     use is_sorted::IsSorted;
-    const TEST_INPUT: &str = r#"0,9 -> 5,9
-8,0 -> 0,8
-9,4 -> 3,4
-2,2 -> 2,1
-7,0 -> 7,4
-6,4 -> 2,0
-0,9 -> 2,9
-3,4 -> 1,4
-0,0 -> 8,8
-5,5 -> 8,2
-"#;
     #[test]
     fn parses_single() {
         let (rest, result) = Line::parse("123,456 -> 78,90").unwrap();
@@ -264,15 +402,15 @@ mod tests {
         assert!(rest.is_empty());
     }
     #[test]
-    fn parses_all() -> Result<(), String> {
-        let all = Line::parse_all(TEST_INPUT)?;
+    fn parses_all() -> Result<(), AocError> {
+        let all = Line::parse_all(EXAMPLE)?;
         assert_eq!(10, all.len());
         Ok(())
     }
 
     #[test]
-    fn partitions_are_sorted() -> Result<(), String> {
-        let lines = Line::parse_all(TEST_INPUT)?;
+    fn partitions_are_sorted() -> Result<(), AocError> {
+        let lines = Line::parse_all(EXAMPLE)?;
         let (mut x, mut y, _) = make_partitioins(&lines);
         assert!(IsSorted::is_sorted_by_key(&mut x.iter_mut(), |u| u.1));
         assert!(IsSorted::is_sorted_by_key(&mut y.iter_mut(), |u| u.1));
@@ -296,7 +434,35 @@ mod tests {
 
     #[test]
     fn gets_part_1() {
-        assert_eq!(5, part1(TEST_INPUT))
+        assert_eq!(Ok(5), Day05.part1(EXAMPLE))
+    }
+
+    #[test]
+    fn solves_from_reader() {
+        assert_eq!(Ok(5), part1_reader(EXAMPLE.as_bytes()));
+        assert_eq!(Ok(12), part2_reader(EXAMPLE.as_bytes()));
+        assert_eq!(Day05.part1(INPUT), part1_reader(INPUT.as_bytes()));
+        assert_eq!(Day05.part2(INPUT), part2_reader(INPUT.as_bytes()));
+        assert_eq!(
+            Err(AocError::parse(5, 2, 4, "expected 'x1,y1 -> x2,y2'")),
+            part2_reader("0,9 -> 5,9\r\n8,0 => 0,8\r\n".as_bytes())
+        );
+    }
+
+    #[test]
+    fn locates_parse_errors() {
+        assert_eq!(
+            Err(AocError::parse(5, 2, 4, "expected 'x1,y1 -> x2,y2'")),
+            Line::parse_all("0,9 -> 5,9\n8,0 => 0,8\n")
+        );
+        assert!(matches!(
+            Line::parse_all("0,9 -> 5,9\n0,0 -> 1,2\n"),
+            Err(AocError::Parse {
+                line: 2,
+                column: 1,
+                ..
+            })
+        ));
     }
 
     #[test]
@@ -384,32 +550,69 @@ mod tests {
 
     #[test]
     fn gets_expected_intersections() {
-        let lines = Line::parse_all(TEST_INPUT).unwrap();
+        let lines = Line::parse_all(EXAMPLE).unwrap();
         let intersections = do_map_things(&lines);
-        let mut s = String::new();
-        for j in 0..=9u32 {
-            for i in 0..=9u32 {
-                let c = if let Some(count) = intersections.get(&(i, j)) {
-                    if count > &9 {
-                        '*'
-                    } else {
-                        char::from_digit(*count as u32, 10).unwrap()
-                    }
-                } else {
-                    '.'
-                };
-                s.push(c);
-            }
-            s.push('\n');
-        }
-        print!("{}", s);
+        assert_eq!(
+            vec![
+                "1.1....11.",
+                ".111...2..",
+                "..2.1.111.",
+                "...1.2.2..",
+                ".112313211",
+                "...1.2....",
+                "..1...1...",
+                ".1.....1..",
+                "1.......1.",
+                "222111....",
+            ],
+            diagram(&intersections)
+        );
         assert!(intersections.get(&(2, 0)).unwrap_or(&0) == &1);
         assert!(intersections.get(&(5u32, 3u32)).unwrap_or(&0) > &1);
         assert!(intersections.get(&(5u32, 5u32)).unwrap_or(&0) > &1);
     }
 
+    #[test]
+    fn traces_diagram() {
+        let (_, events) = trace::capture(|| Day05.part2(EXAMPLE));
+        let rows = trace::named(&events, "row").collect::<Vec<_>>();
+        assert_eq!(10, rows.len());
+        assert_eq!(("4", ".112313211"), (rows[4]["y"], rows[4]["cells"]));
+    }
+
+    #[test]
+    fn rejects_large_coordinates() {
+        let message = "coordinates must be at most 1073741823";
+        assert_eq!(
+            Err(AocError::parse(5, 1, 1, message)),
+            Day05.part2("2147483647,0 -> 2147483648,1\n")
+        );
+        assert_eq!(
+            Err(AocError::parse(5, 1, 1, message)),
+            Day05.part1("4294967295,4294967295 -> 4294967294,4294967294\n")
+        );
+        assert_eq!(
+            Err(AocError::parse(5, 2, 19, message)),
+            Line::parse_all("0,0 -> 1,1\n0,1073741823 -> 0,1073741824\n")
+        );
+        // the largest coordinates cross without overflow.
+        let corner = "1073741823,1073741823 -> 1073741821,1073741821\n\
+            1073741821,1073741823 -> 1073741823,1073741821\n";
+        assert_eq!(Ok(0), Day05.part1(corner));
+        assert_eq!(Ok(1), Day05.part2(corner));
+        assert_eq!(Ok(1), part2_reader(corner.as_bytes()));
+    }
+
+    #[test]
+    fn traces_without_drawing_huge_grids() {
+        let input = "1073741823,0 -> 1073741823,5\n";
+        let (answer, events) = trace::capture(|| Day05.part2(input));
+        assert_eq!(Day05.part2(input), answer);
+        assert_eq!(0, trace::named(&events, "row").count());
+    }
+
     #[test]
     fn gets_part_2() {
-        assert_eq!(12, part2(TEST_INPUT));
+        assert_eq!(Ok(12), Day05.part2(EXAMPLE));
     }
 }
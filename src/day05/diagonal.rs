@@ -26,10 +26,12 @@ impl Diagonal {
         .take(self.length.try_into().unwrap())
     }
 
+    #[allow(clippy::reversed_empty_ranges)]
     fn intersection_range(&self, other: &Diagonal) -> RangeInclusive<u32> {
         if self.is_positive_y == other.is_positive_y {
             // either (positive) -x + y == c, or (negative) x + y == c.
             // if c's are equal, the lines are colinear.
+            // coordinates are at most MAX_COORDINATE, so their sum fits.
             fn c_coefficient(d: &Diagonal) -> i32 {
                 if d.is_positive_y {
                     -(d.start.0 as i32) + (d.start.1 as i32)
@@ -38,11 +40,11 @@ impl Diagonal {
                 }
             }
             if c_coefficient(self) == c_coefficient(other) {
-                let overlap_range = RangeInclusive::new(
+                RangeInclusive::new(
                     self.start.0.max(other.start.0) - self.start.0,
-                    (self.start.0 + self.length - 1).min(other.start.0 + other.length - 1) - self.start.0
-                );
-                overlap_range
+                    (self.start.0 + self.length - 1).min(other.start.0 + other.length - 1)
+                        - self.start.0,
+                )
             } else {
                 1..=0
             }
@@ -84,7 +86,12 @@ impl Diagonal {
         let intersection_range = self.intersection_range(other);
         if !intersection_range.is_empty() {
             assert!(intersection_range.start() >= &0);
-            assert!(intersection_range.end() <= &self.length, "Range {:?} lies outside bounds of self {:?}", intersection_range, self);
+            assert!(
+                intersection_range.end() <= &self.length,
+                "Range {:?} lies outside bounds of self {:?}",
+                intersection_range,
+                self
+            );
         }
         intersection_range.map(move |offset| {
             (
@@ -102,9 +109,10 @@ impl Diagonal {
 #[cfg(test)]
 mod tests {
     #[test]
+    #[allow(clippy::reversed_empty_ranges)]
     fn range_can_be_inside_out() {
         let r = 10..=0;
-        let v = r.clone().map(|i|i).collect::<Vec<_>>();
+        let v = r.clone().collect::<Vec<_>>();
         assert!(v.is_empty());
         assert!(r.is_empty());
     }
@@ -1,69 +1,187 @@
-pub fn part1(input: &str) -> usize {
-    let board = Board::parse(input);
-    let board = board.step_many(80);
-    board.count()
-}
-pub fn part2(input: &str) -> usize {
-    let board = Board::parse(input);
-    let board = board.step_many(256);
-    board.count()
+use std::collections::HashMap;
+
+use crate::{
+    error::{parse_number, AocError},
+    generate::Rng,
+    solution::{Answer, Part, Puzzle},
+    trace,
+};
+
+pub struct Day06;
+impl Puzzle for Day06 {
+    const DAY: u32 = 6;
+    const NAME: &'static str = "Lanternfish";
+    const INPUT: &'static str = INPUT;
+
+    type Parsed<'a> = Board;
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, AocError> {
+        Board::parse(input)
+    }
+    fn part1(board: &Board) -> Result<Answer, AocError> {
+        let board = board.step_many(80);
+        Ok(board.count() as Answer)
+    }
+    fn part2(board: &Board) -> Result<Answer, AocError> {
+        let board = board.step_many(256);
+        Ok(board.count() as Answer)
+    }
+    fn reference(board: &Board, part: Part) -> Result<Answer, AocError> {
+        reference(board, part)
+    }
 }
 
 pub const INPUT: &str = include_str!("day06/input.txt");
+pub const EXAMPLE: &str = include_str!("day06/example.txt");
 
 #[derive(Debug, PartialEq)]
-struct Board([usize; 9]);
+pub struct Board([usize; 9]);
 
 impl Board {
     const BIRTH_CYCLE: usize = 6;
-    fn step(&self) -> Self {
-        let mut next = self.0.clone();
+    pub fn step(&self) -> Self {
+        let mut next = self.0;
         next.rotate_left(1);
         let births = next[next.len() - 1];
         next[Self::BIRTH_CYCLE] += births;
         Self(next)
     }
-    fn step_many(&self, count: usize) -> Self {
-        let mut value = None;
-        for _ in 0..count {
-            value = Some(value.as_ref().unwrap_or(self).step());
+    /// Advances by `count` days.
+    pub fn step_many(&self, count: usize) -> Self {
+        let mut value = Self(self.0);
+        for day in 1..=count {
+            let next = value.step();
+            trace::emit("day", || {
+                let ages = next.0.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+                vec![
+                    ("day", day.to_string()),
+                    ("ages", ages.join(",")),
+                    ("fish", next.count().to_string()),
+                ]
+            });
+            value = next;
         }
-        value.unwrap()
+        value
     }
-    fn count(&self) -> usize {
+    /// The number of fish.
+    pub fn count(&self) -> usize {
         self.0.iter().sum()
     }
-    fn parse(line: &str) -> Self {
+    pub fn parse(input: &str) -> Result<Self, AocError> {
         let line = {
-            let mut lines = line.lines();
-            let l = lines.next().expect("No newline in source");
+            let mut lines = input.lines();
+            let l = lines
+                .next()
+                .ok_or_else(|| AocError::invalid(Day06::DAY, "no fish in input"))?;
             if let Some(second) = lines.next() {
                 if !second.is_empty() {
-                    panic!("Unexpected second line of input");
+                    return Err(AocError::at(
+                        Day06::DAY,
+                        input,
+                        second,
+                        "unexpected second line of input",
+                    ));
                 }
             }
             l
         };
         let mut output = [0; 9];
-        for token in line.split(',').map(|s| s.parse::<usize>()) {
-            let token = token.unwrap();
+        for s in line.split(',') {
+            let token: usize = parse_number(Day06::DAY, input, s)?;
             if token > 8 {
-                panic!("Unexpected age out of bounds: {}", token);
+                return Err(AocError::at(
+                    Day06::DAY,
+                    input,
+                    s,
+                    format!("age {} is out of bounds", token),
+                ));
             }
             output[token] += 1;
         }
-        Self(output)
+        Ok(Self(output))
+    }
+}
+
+/// Writes the ages of `size` fish, from 1 to 5 as in the puzzle inputs.
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let ages = (0..size)
+        .map(|_| rng.range(1..=5).to_string())
+        .collect::<Vec<_>>();
+    ages.join(",") + "\n"
+}
+
+/// Follows every fish for part 1, and counts each fish's descendants for part 2.
+fn reference(board: &Board, part: Part) -> Result<Answer, AocError> {
+    match part {
+        Part::One => {
+            let mut fish = (0..board.0.len())
+                .flat_map(|age| std::iter::repeat_n(age, board.0[age]))
+                .collect::<Vec<_>>();
+            for _ in 0..80 {
+                let births = fish.iter().filter(|&&age| age == 0).count();
+                for age in fish.iter_mut() {
+                    *age = if *age == 0 {
+                        Board::BIRTH_CYCLE
+                    } else {
+                        *age - 1
+                    };
+                }
+                fish.extend(std::iter::repeat_n(8, births));
+            }
+            Ok(fish.len() as Answer)
+        }
+        Part::Two => {
+            /// The fish which one fish of `age` becomes after `days`.
+            fn family(
+                age: usize,
+                days: usize,
+                known: &mut HashMap<(usize, usize), Answer>,
+            ) -> Answer {
+                if days <= age {
+                    return 1;
+                }
+                if let Some(&n) = known.get(&(age, days)) {
+                    return n;
+                }
+                let left = days - age - 1;
+                let n = family(Board::BIRTH_CYCLE, left, known) + family(8, left, known);
+                known.insert((age, days), n);
+                n
+            }
+            let mut known = HashMap::new();
+            Ok((0..board.0.len())
+                .map(|age| board.0[age] as Answer * family(age, 256, &mut known))
+                .sum())
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::solution::Solution;
+
+    #[test]
+    fn traces_ages() {
+        let (_, events) = trace::capture(|| Day06.part1(EXAMPLE));
+        let days = trace::named(&events, "day").collect::<Vec<_>>();
+        assert_eq!(80, days.len());
+        assert_eq!("1,1,2,1,0,0,0,0,0", days[0]["ages"]);
+        assert_eq!(("18", "26"), (days[17]["day"], days[17]["fish"]));
+    }
 
     #[test]
     fn does_parse() {
         let board = Board::parse("3,4,3,1,2\n");
-        assert_eq!(Board([0, 1, 1, 2, 1, 0, 0, 0, 0]), board);
+        assert_eq!(Ok(Board([0, 1, 1, 2, 1, 0, 0, 0, 0])), board);
+    }
+
+    #[test]
+    fn rejects_bad_ages() {
+        assert_eq!(
+            Err(AocError::parse(6, 1, 5, "age 9 is out of bounds")),
+            Board::parse("3,4,9,1,2")
+        );
+        assert!(Board::parse("3,4\n5").is_err());
     }
 
     #[test]
@@ -80,22 +198,22 @@ mod tests {
 
     #[test]
     fn steps_many() {
-        let initial = "3,4,3,1,2";
+        let initial = EXAMPLE;
         let expected = "6,0,6,4,5,6,0,1,1,2,6,0,1,1,1,2,2,3,3,4,6,7,8,8,8,8";
 
-        let board = Board::parse(initial);
+        let board = Board::parse(initial).unwrap();
         let board = board.step_many(18);
-        assert_eq!(Board::parse(expected), board);
-        assert_eq!(26, board.count());
+        assert_eq!(Ok(board), Board::parse(expected));
+        assert_eq!(26, Board::parse(expected).unwrap().count());
+        let initial = Board::parse(initial).unwrap();
+        assert_eq!(initial, initial.step_many(0));
     }
     #[test]
     fn gets_part_1() {
-        let initial = "3,4,3,1,2";
-        assert_eq!(5934, part1(initial));
+        assert_eq!(Ok(5934), Day06.part1(EXAMPLE));
     }
     #[test]
     fn gets_part_2() {
-        let initial = "3,4,3,1,2";
-        assert_eq!(26984457539, part2(initial));
+        assert_eq!(Ok(26984457539), Day06.part2(EXAMPLE));
     }
 }
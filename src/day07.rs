@@ -1,22 +1,65 @@
+use std::convert::TryFrom;
+
+use crate::{
+    error::{parse_number, AocError},
+    generate::Rng,
+    solution::{Answer, Part, Puzzle},
+};
+
 pub const INPUT: &str = include_str!("day07/input.txt");
-pub fn part1(input: &str) -> u64 {
-    find(input, get_cost_1)
-}
-pub fn part2(input: &str) -> u64 {
-    find(input, get_cost_2)
+pub const EXAMPLE: &str = include_str!("day07/example.txt");
+pub struct Day07;
+impl Puzzle for Day07 {
+    const DAY: u32 = 7;
+    const NAME: &'static str = "The Treachery of Whales";
+    const INPUT: &'static str = INPUT;
+    const VERSION: u32 = 2;
+
+    type Parsed<'a> = Vec<u64>;
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, AocError> {
+        parse_positions(input)
+    }
+    fn part1(positions: &Vec<u64>) -> Result<Answer, AocError> {
+        find(positions, Part::One)
+    }
+    fn part2(positions: &Vec<u64>) -> Result<Answer, AocError> {
+        find(positions, Part::Two)
+    }
+    fn reference(positions: &Vec<u64>, part: Part) -> Result<Answer, AocError> {
+        reference(positions, part)
+    }
 }
-fn find(input: &str, cost: impl Fn(u64, u64) -> u64) -> u64 {
+
+/// Gets the sorted crab positions.
+pub fn parse_positions(input: &str) -> Result<Vec<u64>, AocError> {
     let line = {
         let mut lines = input.lines();
-        let l = lines.next().expect("No newline in source");
+        let l = lines
+            .next()
+            .ok_or_else(|| AocError::invalid(Day07::DAY, "no crabs in input"))?;
         if let Some(second) = lines.next() {
             if !second.is_empty() {
-                panic!("Unexpected second line of input");
+                return Err(AocError::at(
+                    Day07::DAY,
+                    input,
+                    second,
+                    "unexpected second line of input",
+                ));
             }
         }
         l
     };
+    let mut items = line
+        .split(',')
+        .map(|s| parse_number(Day07::DAY, input, s))
+        .collect::<Result<Vec<u64>, _>>()?;
+    items.sort_unstable();
+    Ok(items)
+}
 
+/// Finds the lowest total cost of moving every (sorted) crab to one position,
+/// trying only the positions which could be cheapest.
+pub fn find(items: &[u64], part: Part) -> Result<u64, AocError> {
     // ==PART 1==
     // let x' be the answer, then
     // cost c == SUM_i{|x - x'|} is minimised
@@ -24,57 +67,166 @@ fn find(input: &str, cost: impl Fn(u64, u64) -> u64) -> u64 {
     // partitioned around x', then
     // c = SUM{x' - a..e} + SUM{v..z - x'}
     //   = m x' - SUM{a..e} + SUM(v..z) - n x'
+    // which no longer falls once as many crabs are left of x' as right: the median.
 
     // ==PART 2==
     // cost per element is SUM_i=0^i=|x'-x| { i },
     // or ((d+1)^2 - d+1) / 2
     // or (d^2 + d) / 2
+    // whose sum has slope SUM{x' - x} + SUM{sign(x' - x)} / 2, zero within
+    // half a step of the mean, so the cheapest whole position is at most one
+    // step further.
 
-    let mut items = line
-        .split(',')
-        .map(|s| s.parse::<u64>().unwrap())
-        .collect::<Vec<_>>();
-    items.sort();
-
-    let (_pivot, cost) = min_search(&items, cost);
-    cost
-}
-
-fn min_search(items: &[u64], cost: impl Fn(u64, u64) -> u64) -> (u64, u64) {
-    let pivots = items[0]..;
-    let mut costs = pivots
-        .map(|p| (p, items.iter().map(|&i| cost(p, i)).sum()))
-        .peekable();
-    loop {
-        if let (Some(c), Some(d)) = (costs.next(), costs.peek()) {
-            if d.1 > c.1 {
-                return c;
-            }
-        } else {
-            panic!("Local minimum not found before end of collection.");
-        }
+    if items.is_empty() {
+        return Err(AocError::invalid(Day07::DAY, "no crabs in input"));
     }
+    let pivots = match part {
+        Part::One => {
+            let median = items[(items.len() - 1) / 2];
+            median..=median
+        }
+        Part::Two => {
+            let n = items.len() as u128;
+            let sum = items.iter().map(|&i| u128::from(i)).sum::<u128>();
+            // floor(mean - 1/2) and ceil(mean + 1/2), within the crabs.
+            let low = (2 * sum).saturating_sub(n) / (2 * n);
+            let high = (2 * sum + n).div_ceil(2 * n);
+            let (first, last) = (items[0], items[items.len() - 1]);
+            // both casts are within the crabs' positions, so can't truncate.
+            (low.max(first.into()) as u64)..=(high.min(last.into()) as u64)
+        }
+    };
+    let cost = match part {
+        Part::One => |p, i| Some(get_cost_1(p, i)),
+        Part::Two => get_cost_2,
+    };
+    pivots
+        .map(|p| {
+            items
+                .iter()
+                .try_fold(0u64, |total, &i| total.checked_add(cost(p, i)?))
+                .ok_or_else(|| {
+                    AocError::unsolvable(Day07::DAY, "the fuel used is too large to count")
+                })
+        })
+        .try_fold(u64::MAX, |best, cost| Ok(best.min(cost?)))
 }
 
-fn get_cost_1(pivot: u64, item: u64) -> u64 {
-    item.checked_sub(pivot).or(pivot.checked_sub(item)).unwrap()
+/// The fuel used to move one crab, at one unit per step.
+pub fn get_cost_1(pivot: u64, item: u64) -> u64 {
+    item.max(pivot) - item.min(pivot)
 }
-fn get_cost_2(pivot: u64, item: u64) -> u64 {
+/// The fuel used to move one crab, where each step costs one more than the
+/// last, or `None` if it is too large to count.
+pub fn get_cost_2(pivot: u64, item: u64) -> Option<u64> {
     let difference = get_cost_1(pivot, item);
-    ((difference + 1) * difference) / 2
+    // one of the two is even, so halve it first.
+    match difference % 2 {
+        0 => (difference / 2).checked_mul(difference + 1),
+        _ => difference.checked_mul(difference / 2 + 1),
+    }
+}
+
+/// Writes the positions of `size` crabs, with more crabs nearer to 0 as in the puzzle inputs.
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    let furthest = (size as u32).saturating_mul(2).max(16);
+    let positions = (0..size)
+        .map(|_| {
+            rng.range(0..=furthest)
+                .min(rng.range(0..=furthest))
+                .to_string()
+        })
+        .collect::<Vec<_>>();
+    positions.join(",") + "\n"
 }
 
+/// Tries every position between the furthest crabs, counting in 128 bits so
+/// that nothing can overflow.
+fn reference(positions: &[u64], part: Part) -> Result<Answer, AocError> {
+    let cost = |distance: u64| match part {
+        Part::One => u128::from(distance),
+        Part::Two => u128::from(distance) * (u128::from(distance) + 1) / 2,
+    };
+    let (first, last) = match (positions.iter().min(), positions.iter().max()) {
+        (Some(&first), Some(&last)) => (first, last),
+        _ => return Err(AocError::invalid(Day07::DAY, "no crabs in input")),
+    };
+    let cheapest = (first..=last)
+        .map(|p| {
+            positions
+                .iter()
+                .map(|&x| cost(x.max(p) - x.min(p)))
+                .sum::<u128>()
+        })
+        .min()
+        .expect("at least one position");
+    Answer::try_from(cheapest)
+        .map_err(|_| AocError::unsolvable(Day07::DAY, "the fuel used is too large to count"))
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::solution::Solution;
 
     #[test]
     fn gets_part_1() {
-        assert_eq!(part1("16,1,2,0,4,2,7,1,2,14"), 37)
+        assert_eq!(Day07.part1(EXAMPLE), Ok(37))
     }
     #[test]
     fn gets_part_2() {
-        assert_eq!(part2("16,1,2,0,4,2,7,1,2,14"), 168)
+        assert_eq!(Day07.part2(EXAMPLE), Ok(168))
+    }
+    #[test]
+    fn rejects_bad_positions() {
+        assert_eq!(
+            Err(AocError::parse(
+                7,
+                1,
+                4,
+                "'x' is not a valid number: invalid digit found in string"
+            )),
+            parse_positions("16,x,2")
+        );
+        assert!(matches!(
+            parse_positions(""),
+            Err(AocError::InvalidInput { .. })
+        ));
+        assert!(find(&[], Part::One).is_err());
+    }
+    #[test]
+    fn finds_distant_crabs_quickly() {
+        let positions = parse_positions("0,7000000000").unwrap();
+        assert_eq!(Ok(7_000_000_000), find(&positions, Part::One));
+        assert_eq!(
+            Ok(3_500_000_000 * 3_500_000_001),
+            find(&positions, Part::Two)
+        );
+        let positions = parse_positions("0,7000000000,7000000000").unwrap();
+        assert_eq!(Ok(7_000_000_000), find(&positions, Part::One));
+    }
+    #[test]
+    fn rejects_too_much_fuel() {
+        assert_eq!(Ok(0), Day07.part1("18446744073709551615"));
+        assert_eq!(Ok(0), Day07.part2("18446744073709551615\n"));
+        let too_much = Err(AocError::unsolvable(
+            7,
+            "the fuel used is too large to count",
+        ));
+        assert_eq!(
+            too_much,
+            Day07.part1("0,18446744073709551615,18446744073709551615,0")
+        );
+        assert_eq!(too_much, Day07.part2("0,10000000000"));
+    }
+    #[test]
+    fn matches_every_position() {
+        let mut rng = Rng::new(7);
+        for size in 1..40 {
+            let positions = parse_positions(&generate(&mut rng, size)).unwrap();
+            for &part in &[Part::One, Part::Two] {
+                assert_eq!(reference(&positions, part), find(&positions, part));
+            }
+        }
     }
 }
@@ -1,49 +1,114 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::{
+    error::AocError,
+    generate::Rng,
+    solution::{Answer, Part, Puzzle},
+    trace,
+};
+
 pub const INPUT: &str = include_str!("day08/input.txt");
-pub fn part1(input: &str) -> usize {
-    let entries = input
-        .lines()
-        .take_while(|l| !l.is_empty())
-        .map(Entry::parse);
-    entries
-        .map(|e| {
-            e.output
-                .iter()
-                .filter(|d| d.infer_value().is_some())
-                .count()
-        })
-        .sum()
-}
-pub fn part2(input: &str) -> usize {
-    let entries = input
-        .lines()
-        .take_while(|l| !l.is_empty())
-        .map(Entry::parse);
-    entries
-        .map(|mut e| {
-            e.solve();
-            e.get_output()
-        })
-        .sum()
+pub const EXAMPLE: &str = include_str!("day08/example.txt");
+/// A single entry, from the start of the puzzle description.
+pub const EXAMPLE_ENTRY: &str = include_str!("day08/example_entry.txt");
+pub struct Day08;
+impl Puzzle for Day08 {
+    const DAY: u32 = 8;
+    const NAME: &'static str = "Seven Segment Search";
+    const INPUT: &'static str = INPUT;
+    const VERSION: u32 = 2;
+
+    type Parsed<'a> = Vec<Entry>;
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, AocError> {
+        input
+            .lines()
+            .enumerate()
+            .map(|(i, line)| match line {
+                "" => Err(AocError::parse(
+                    Day08::DAY,
+                    i + 1,
+                    1,
+                    "unexpected blank line",
+                )),
+                _ => Entry::parse(line).map_err(|e| e.offset_lines(i)),
+            })
+            .collect()
+    }
+    fn part1(entries: &Vec<Entry>) -> Result<Answer, AocError> {
+        Ok(entries
+            .iter()
+            .map(|e| {
+                e.output
+                    .iter()
+                    .filter(|d| d.infer_value().is_some())
+                    .count() as Answer
+            })
+            .sum())
+    }
+    fn part2(entries: &Vec<Entry>) -> Result<Answer, AocError> {
+        entries
+            .iter()
+            .enumerate()
+            .map(|(i, e)| {
+                let mut e = e.clone();
+                e.solve()?;
+                let output = e.get_output()?;
+                trace::emit("entry", || {
+                    const NAMES: [&str; 10] = ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"];
+                    let mut fields = vec![("entry", (i + 1).to_string())];
+                    fields.extend(
+                        NAMES
+                            .iter()
+                            .zip(&e.reference)
+                            .map(|(&n, d)| (n, d.to_string())),
+                    );
+                    fields.push(("output", output.to_string()));
+                    fields
+                });
+                Ok(output as Answer)
+            })
+            .sum()
+    }
+    fn reference(entries: &Vec<Entry>, part: Part) -> Result<Answer, AocError> {
+        reference(entries, part)
+    }
 }
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
 struct Digit(u8);
 impl Digit {
     const DEFAULT: Digit = Digit(0);
 }
-impl Digit {
-    fn from_str(input: &str) -> Self {
+/// The segments which are lit, as in the input.
+impl Display for Digit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, segment) in ('a'..='g').enumerate() {
+            if self.0 & (1 << i) != 0 {
+                write!(f, "{}", segment)?;
+            }
+        }
+        Ok(())
+    }
+}
+impl FromStr for Digit {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
         if input.len() > 7 {
-            panic!("Unexpectedly-long string");
+            return Err(format!("'{}' has more than seven segments", input));
         }
         let mut output = 0;
         for b in input.bytes() {
+            if !(b'a'..=b'g').contains(&b) {
+                return Err(format!("'{}' has segments outside 'a' to 'g'", input));
+            }
             let bit = b - b'a';
             output |= 1u8 << bit;
         }
-        Self(output)
+        Ok(Self(output))
     }
-
+}
+impl Digit {
     fn infer_value(&self) -> Option<u8> {
         match self.0.count_ones() {
             2 => Some(1),
@@ -54,34 +119,43 @@ impl Digit {
         }
     }
 
-    fn categorise_six_segment(&self, one: &Digit, four: &Digit) -> u8 {
+    fn categorise_six_segment(&self, one: &Digit, four: &Digit) -> Result<u8, AocError> {
         match (self.0 & four.0).count_ones() {
-            4 => 9,
+            4 => Ok(9),
             3 => match (self.0 & one.0).count_ones() {
-                2 => 0,
-                1 => 6,
-                _ => panic!("Illegal bit representation."),
+                2 => Ok(0),
+                1 => Ok(6),
+                _ => Err(self.illegal()),
             },
-            _ => panic!("Illegal bit representation."),
+            _ => Err(self.illegal()),
         }
     }
-    fn categorise_five_segment(&self, one: &Digit, six: &Digit) -> u8 {
+    fn categorise_five_segment(&self, one: &Digit, six: &Digit) -> Result<u8, AocError> {
         match (self.0 & one.0).count_ones() {
-            2 => 3,
+            2 => Ok(3),
             1 => match (self.0 & six.0).count_ones() {
-                5 => 5,
-                4 => 2,
-                _ => panic!(
-                    "Illegal 5-bit representation {:#b}, given 1:{:#b}, 6:{:#b}.",
-                    self.0, one.0, six.0
-                ),
+                5 => Ok(5),
+                4 => Ok(2),
+                _ => Err(AocError::unsolvable(
+                    Day08::DAY,
+                    format!(
+                        "illegal 5-bit representation {:#b}, given 1:{:#b}, 6:{:#b}",
+                        self.0, one.0, six.0
+                    ),
+                )),
             },
-            _ => panic!("Illegal bit representation."),
+            _ => Err(self.illegal()),
         }
     }
+    fn illegal(&self) -> AocError {
+        AocError::unsolvable(
+            Day08::DAY,
+            format!("illegal bit representation {:#b}", self.0),
+        )
+    }
 }
-#[derive(PartialEq, Debug)]
-struct Entry {
+#[derive(PartialEq, Debug, Clone)]
+pub struct Entry {
     reference: [Digit; Self::REFERENCE_LENGTH],
     output: [Digit; Self::OUTPUT_LENGTH],
 }
@@ -89,30 +163,48 @@ struct Entry {
 impl Entry {
     const REFERENCE_LENGTH: usize = 10;
     const OUTPUT_LENGTH: usize = 4;
-    fn parse(line: &str) -> Entry {
+    /// Parses a single line. Parse errors are reported as if it were the first line.
+    pub fn parse(line: &str) -> Result<Entry, AocError> {
         let mut tokens = line.split_ascii_whitespace();
         let mut output_left = [Digit::DEFAULT; 10];
         let mut output_right = [Digit::DEFAULT; 4];
+        let end = &line[line.len()..];
 
-        for i in 0..10 {
-            let token = tokens.next().unwrap();
-            output_left[i] = Digit::from_str(token);
+        for digit in output_left.iter_mut() {
+            *digit = next_digit(line, &mut tokens)?;
+        }
+        match tokens.next() {
+            Some("|") => (),
+            other => {
+                return Err(AocError::at(
+                    Day08::DAY,
+                    line,
+                    other.unwrap_or(end),
+                    "missing expected central delimiter",
+                ))
+            }
         }
-        if tokens.next() != Some("|") {
-            panic!("Missing expected central delimiter");
+        for digit in output_right.iter_mut() {
+            *digit = next_digit(line, &mut tokens)?;
         }
-        for i in 0..4 {
-            let token = tokens.next().unwrap();
-            output_right[i] = Digit::from_str(token);
+        if let Some(extra) = tokens.next() {
+            return Err(AocError::at(
+                Day08::DAY,
+                line,
+                extra,
+                "unexpected text after the four output digits",
+            ));
         }
 
-        Self {
+        Ok(Self {
             reference: output_left,
             output: output_right,
-        }
+        })
     }
 
-    fn solve(&mut self) {
+    /// Works out which reference pattern shows which digit, and reorders the
+    /// patterns so that each is at the index of its value.
+    pub fn solve(&mut self) -> Result<(), AocError> {
         // Asking for [None; 10] gives E0277 because it doesn't know how to copy Some(Digit(asdf)).
         // Firstly: you don't need to fucking copy a digit because (checks notes) None is not a Digit.
         // Secondly: [repeats None 10 times] this is the exact fucking code you'd want to generate?
@@ -125,37 +217,48 @@ impl Entry {
             if let Some(value) = i.infer_value() {
                 scratch[value as usize] = Some(Digit(i.0));
                 match value {
-                    1 => one = Some(i.clone()),
-                    4 => four = Some(i.clone()),
+                    1 => one = Some(i),
+                    4 => four = Some(i),
                     _ => (),
                 };
             }
         }
-        let one = one.unwrap();
-        let four = four.unwrap();
+        let missing = |digit| AocError::unsolvable(Day08::DAY, format!("no pattern for {}", digit));
+        let one = one.ok_or_else(|| missing(1))?;
+        let four = four.ok_or_else(|| missing(4))?;
         for i in self.reference.iter().filter(|d| d.0.count_ones() == 6) {
-            let value = i.categorise_six_segment(one, four);
+            let value = i.categorise_six_segment(one, four)?;
             scratch[value as usize] = Some(Digit(i.0));
             if value == 6 {
-                six = Some(i.clone());
+                six = Some(i);
             }
         }
-        let six = six.unwrap();
+        let six = six.ok_or_else(|| missing(6))?;
         for i in self.reference.iter().filter(|d| d.0.count_ones() == 5) {
-            scratch[i.categorise_five_segment(one, six) as usize] = Some(Digit(i.0));
+            scratch[i.categorise_five_segment(one, six)? as usize] = Some(Digit(i.0));
         }
 
-        for i in 0..Self::REFERENCE_LENGTH {
-            self.reference[i] = Digit(scratch[i].as_ref().unwrap().0);
+        let mut solved = [Digit::DEFAULT; 10];
+        for (value, (digit, found)) in solved.iter_mut().zip(scratch.iter()).enumerate() {
+            *digit = Digit(found.as_ref().ok_or_else(|| missing(value))?.0);
         }
+        self.reference = solved;
+        Ok(())
     }
 
-    fn get_output(&self) -> usize {
+    /// Reads the four-digit output value. The entry must be solved first.
+    pub fn get_output(&self) -> Result<usize, AocError> {
         // check we have now emplaced all the integers.
-        assert!([6, 2, 5, 5, 4, 5, 6, 3, 7, 6]
+        if ![6, 2, 5, 5, 4, 5, 6, 3, 7, 6]
             .iter()
             .copied()
-            .eq(self.reference.iter().map(|d| d.0.count_ones())));
+            .eq(self.reference.iter().map(|d| d.0.count_ones()))
+        {
+            return Err(AocError::unsolvable(
+                Day08::DAY,
+                "reference patterns are not all distinct digits",
+            ));
+        }
 
         let mut output = 0;
         for i in &self.output {
@@ -163,56 +266,150 @@ impl Entry {
             output += self
                 .reference
                 .iter()
-                .enumerate()
-                .find(|(_, digit)| digit == &i)
-                .unwrap()
-                .0;
+                .position(|digit| digit == i)
+                .ok_or_else(|| {
+                    AocError::unsolvable(
+                        Day08::DAY,
+                        format!("output {:#b} is not a reference pattern", i.0),
+                    )
+                })?;
         }
-        output
+        Ok(output)
     }
 }
 
+/// Parses the next digit of a line, which is reported as if it were the first line.
+fn next_digit<'a>(
+    line: &'a str,
+    tokens: &mut impl Iterator<Item = &'a str>,
+) -> Result<Digit, AocError> {
+    let token = tokens.next().ok_or_else(|| {
+        AocError::at(
+            Day08::DAY,
+            line,
+            &line[line.len()..],
+            "expected another digit",
+        )
+    })?;
+    token
+        .parse()
+        .map_err(|message| AocError::at(Day08::DAY, line, token, message))
+}
+
+/// Writes `size` entries, each with its own wiring of segments and order of patterns.
+pub fn generate(rng: &mut Rng, size: usize) -> String {
+    const SEGMENTS: [&str; 10] = [
+        "abcefg", "cf", "acdeg", "acdfg", "bcdf", "abdfg", "abdefg", "acf", "abcdefg", "abcdfg",
+    ];
+    let mut output = String::new();
+    for _ in 0..size {
+        let mut wiring = *b"abcdefg";
+        rng.shuffle(&mut wiring);
+        let write = |rng: &mut Rng, digit: usize, output: &mut String| {
+            let mut wires = SEGMENTS[digit]
+                .bytes()
+                .map(|s| wiring[(s - b'a') as usize] as char)
+                .collect::<Vec<_>>();
+            rng.shuffle(&mut wires);
+            output.extend(wires);
+        };
+        let mut patterns = (0..10).collect::<Vec<_>>();
+        rng.shuffle(&mut patterns);
+        for digit in patterns {
+            write(rng, digit, &mut output);
+            output.push(' ');
+        }
+        output.push('|');
+        for _ in 0..Entry::OUTPUT_LENGTH {
+            output.push(' ');
+            let digit = rng.below(10) as usize;
+            write(rng, digit, &mut output);
+        }
+        output.push('\n');
+    }
+    output
+}
+
+/// Tries every wiring of the segments until all ten patterns are digits.
+fn reference(entries: &[Entry], part: Part) -> Result<Answer, AocError> {
+    const DIGITS: [u8; 10] = [
+        0b1110111, 0b0100100, 0b1011101, 0b1101101, 0b0101110, 0b1101011, 0b1111011, 0b0100101,
+        0b1111111, 0b1101111,
+    ];
+    let mut wirings = vec![[0u8; 7]];
+    for n in 1..7 {
+        wirings = wirings
+            .into_iter()
+            .flat_map(|w| {
+                (0..=n).map(move |i| {
+                    let mut next = w;
+                    next.copy_within(i..n, i + 1);
+                    next[i] = n as u8;
+                    next
+                })
+            })
+            .collect();
+    }
+    let mut total = 0;
+    for entry in entries {
+        let decode = |wiring: &[u8; 7], d: &Digit| {
+            let lit = (0..7)
+                .filter(|&i| d.0 & (1 << i) != 0)
+                .fold(0, |prev, i| prev | 1 << wiring[i]);
+            DIGITS.iter().position(|&digit| digit == lit)
+        };
+        let wiring = wirings
+            .iter()
+            .find(|w| {
+                let seen = entry
+                    .reference
+                    .iter()
+                    .filter_map(|d| decode(w, d))
+                    .fold(0u16, |seen, d| seen | 1 << d);
+                seen == (1 << 10) - 1
+            })
+            .ok_or_else(|| AocError::unsolvable(Day08::DAY, "no wiring fits the patterns"))?;
+        let output = entry.output.iter().map(|d| decode(wiring, d).unwrap_or(10));
+        total += match part {
+            Part::One => output.filter(|d| [1, 4, 7, 8].contains(d)).count() as Answer,
+            Part::Two => output.fold(0, |prev, d| prev * 10 + d as Answer),
+        };
+    }
+    Ok(total)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::solution::Solution;
 
-    const TEST_ROW: &str =
-        "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf";
+    fn digit(s: &str) -> Digit {
+        s.parse().unwrap()
+    }
 
     #[test]
     fn can_parse() {
-        let e = Entry::parse(TEST_ROW);
+        let e = Entry::parse(EXAMPLE_ENTRY).unwrap();
         assert_eq!(
             [
-                Digit::from_str("cdfeb"),
-                Digit::from_str("fcadb"),
-                Digit::from_str("cdfeb"),
-                Digit::from_str("cdbaf")
+                digit("cdfeb"),
+                digit("fcadb"),
+                digit("cdfeb"),
+                digit("cdbaf")
             ],
             e.output
         );
         assert!(e.reference.iter().all(|d| !d.0 > 0));
     }
 
-    const TEST_INPUT: &str = r#"be cfbegad cbdgef fgaecd cgeb fdcge agebfd fecdb fabcd edb | fdgacbe cefdb cefbgd gcbe
-edbfga begcd cbg gc gcadebf fbgde acbgfd abcde gfcbed gfec | fcgedb cgb dgebacf gc
-fgaebd cg bdaec gdafb agbcfd gdcbef bgcad gfac gcb cdgabef | cg cg fdcagb cbg
-fbegcd cbd adcefb dageb afcb bc aefdc ecdab fgdeca fcdbega | efabcd cedba gadfec cb
-aecbfdg fbg gf bafeg dbefa fcge gcbea fcaegb dgceab fcbdga | gecf egdcabf bgf bfgea
-fgeab ca afcebg bdacfeg cfaedg gcfdb baec bfadeg bafgc acf | gebdcfa ecba ca fadegcb
-dbcfg fgd bdegcaf fgec aegbdf ecdfab fbedc dacgb gdcebf gf | cefg dcbef fcge gbcadfe
-bdfegc cbegaf gecbf dfcage bdacg ed bedf ced adcbefg gebcd | ed bcgafe cdgba cbgef
-egadfb cdbfeg cegd fecab cgb gbdefca cg fgcdab egfdb bfceg | gbdfcae bgc cg cgb
-gcafb gcf dcaebfg ecagb gf abcdeg gaef cafbge fdbac fegbdc | fgae cfgab fg bagce
-"#;
     #[test]
     fn gets_part_1() {
-        assert_eq!(part1(TEST_INPUT), 26);
+        assert_eq!(Day08.part1(EXAMPLE), Ok(26));
     }
 
     #[test]
     fn can_solve() {
-        let mut sut = Entry::parse(TEST_ROW);
+        let mut sut = Entry::parse(EXAMPLE_ENTRY).unwrap();
         // from the problem statement:
         // So, the unique signal patterns would correspond to the following digits:
         // acedgfb: 8
@@ -226,22 +423,22 @@ gcafb gcf dcaebfg ecagb gf abcdeg gaef cafbge fdbac fegbdc | fgae cfgab fg bagce
         // cagedb: 0
         // ab: 1
 
-        sut.solve();
+        sut.solve().unwrap();
         let expected = [
-            Digit::from_str("cagedb"),
-            Digit::from_str("ab"),
-            Digit::from_str("gcdfa"),
-            Digit::from_str("fbcad"),
-            Digit::from_str("eafb"),
-            Digit::from_str("cdfbe"),
-            Digit::from_str("cdfgeb"),
-            Digit::from_str("dab"),
-            Digit::from_str("acedgfb"),
-            Digit::from_str("cefabd"),
+            digit("cagedb"),
+            digit("ab"),
+            digit("gcdfa"),
+            digit("fbcad"),
+            digit("eafb"),
+            digit("cdfbe"),
+            digit("cdfgeb"),
+            digit("dab"),
+            digit("acedgfb"),
+            digit("cefabd"),
         ];
         assert_eq!(expected, sut.reference);
 
-        let value = sut.get_output();
+        let value = sut.get_output().unwrap();
         assert_eq!(5353, value);
     }
 
@@ -260,21 +457,76 @@ gcafb gcf dcaebfg ecagb gf abcdeg gaef cafbge fdbac fegbdc | fgae cfgab fg bagce
             fgae cfgab fg bagce: 4315
         */
         let expected = vec![8394, 9781, 1197, 9361, 4873, 8418, 4548, 1625, 8717, 4315];
-        let actual = TEST_INPUT
+        let actual = EXAMPLE
             .lines()
             .take_while(|l| !l.is_empty())
-            .map(Entry::parse)
+            .map(|l| Entry::parse(l).unwrap())
             .map(|mut e| {
-                e.solve();
-                e.get_output()
+                e.solve().unwrap();
+                e.get_output().unwrap()
             })
             .collect::<Vec<_>>();
 
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    fn traces_decoded_digits() {
+        let (_, events) = trace::capture(|| Day08.part2(EXAMPLE_ENTRY));
+        let entry = trace::named(&events, "entry").next().unwrap();
+        // as in the puzzle description, with the segments of each pattern sorted.
+        assert_eq!(
+            ("abcdeg", "ab", "abcdf", "abcdefg"),
+            (entry["0"], entry["1"], entry["3"], entry["8"])
+        );
+        assert_eq!("5353", entry["output"]);
+    }
+
     #[test]
     fn gets_part_2() {
-        assert_eq!(61229, part2(TEST_INPUT));
+        assert_eq!(Ok(61229), Day08.part2(EXAMPLE));
+    }
+
+    #[test]
+    fn locates_parse_errors() {
+        let input = EXAMPLE.replacen("cbg gc", "cbg gz", 1);
+        assert!(matches!(
+            Day08::parse(&input),
+            Err(AocError::Parse {
+                line: 2,
+                column: 18,
+                ..
+            })
+        ));
+        assert!(matches!(
+            Entry::parse("ab | cd"),
+            Err(AocError::Parse { column: 4, .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_blank_lines_and_extra_digits() {
+        let first_line_end = EXAMPLE.find('\n').unwrap() + 1;
+        let mut input = EXAMPLE.to_string();
+        input.insert(first_line_end, '\n');
+        assert_eq!(
+            Err(AocError::parse(8, 2, 1, "unexpected blank line")),
+            Day08::parse(&input)
+        );
+        assert_eq!(
+            Err(AocError::parse(8, 2, 1, "unexpected blank line")),
+            Day08.part2(&input)
+        );
+
+        let extra = format!("{} ab", EXAMPLE_ENTRY.trim_end());
+        assert_eq!(
+            Err(AocError::parse(
+                8,
+                1,
+                extra.len() - 1,
+                "unexpected text after the four output digits"
+            )),
+            Entry::parse(&extra)
+        );
     }
 }
@@ -0,0 +1,175 @@
+use std::{fmt::Display, str::FromStr};
+
+/// Why a puzzle could not be answered.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AocError {
+    /// Some text in the input could not be understood.
+    /// Lines and columns count from 1.
+    Parse {
+        day: u32,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// The input was understood, but is not the shape the puzzle requires.
+    InvalidInput { day: u32, message: String },
+    /// The input is valid, but has no answer.
+    Unsolvable { day: u32, message: String },
+    /// The input could not be read.
+    Io { day: u32, message: String },
+}
+
+impl AocError {
+    pub fn parse(day: u32, line: usize, column: usize, message: impl Into<String>) -> Self {
+        Self::Parse {
+            day,
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+    /// A parse error at the start of `part`, which must be a slice of `input`.
+    pub fn at(day: u32, input: &str, part: &str, message: impl Into<String>) -> Self {
+        let (line, column) = locate(input, part);
+        Self::parse(day, line, column, message)
+    }
+    pub fn invalid(day: u32, message: impl Into<String>) -> Self {
+        Self::InvalidInput {
+            day,
+            message: message.into(),
+        }
+    }
+    pub fn unsolvable(day: u32, message: impl Into<String>) -> Self {
+        Self::Unsolvable {
+            day,
+            message: message.into(),
+        }
+    }
+
+    /// Moves a parse error down by some number of lines, for errors found
+    /// when parsing part of the input in isolation.
+    pub fn offset_lines(self, by: usize) -> Self {
+        match self {
+            Self::Parse {
+                day,
+                line,
+                column,
+                message,
+            } => Self::Parse {
+                day,
+                line: line + by,
+                column,
+                message,
+            },
+            other => other,
+        }
+    }
+}
+
+impl Display for AocError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Parse {
+                day,
+                line,
+                column,
+                message,
+            } => write!(
+                f,
+                "day {}, line {}, column {}: {}",
+                day, line, column, message
+            ),
+            Self::InvalidInput { day, message } => {
+                write!(f, "day {}: invalid input: {}", day, message)
+            }
+            Self::Unsolvable { day, message } => write!(f, "day {}: no solution: {}", day, message),
+            Self::Io { day, message } => write!(f, "day {}: couldn't read input: {}", day, message),
+        }
+    }
+}
+
+impl std::error::Error for AocError {}
+
+/// Finds the line and column, counting from 1, at which `part` begins.
+///
+/// `part` must be a slice of `whole`, such as the remaining input of a parser.
+pub fn locate(whole: &str, part: &str) -> (usize, usize) {
+    let offset = (part.as_ptr() as usize)
+        .checked_sub(whole.as_ptr() as usize)
+        .filter(|&o| o <= whole.len())
+        .expect("part must be a slice of whole");
+    let before = &whole[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+/// Parses a number from `token`, which must be a slice of `input`.
+pub fn parse_number<T>(day: u32, input: &str, token: &str) -> Result<T, AocError>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    token.parse().map_err(|e| {
+        AocError::at(
+            day,
+            input,
+            token,
+            format!("'{}' is not a valid number: {}", token, e),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locates_slices() {
+        let whole = "abc\ndef\r\nghi";
+        assert_eq!((1, 1), locate(whole, whole));
+        assert_eq!((1, 3), locate(whole, &whole[2..]));
+        assert_eq!((2, 1), locate(whole, &whole[4..]));
+        assert_eq!((3, 2), locate(whole, &whole[10..]));
+        assert_eq!((3, 4), locate(whole, &whole[whole.len()..]));
+    }
+
+    #[test]
+    fn parses_numbers() {
+        let input = "12\n3x4";
+        assert_eq!(Ok(12u32), parse_number(1, input, &input[..2]));
+        assert_eq!(
+            Some((2, 1)),
+            match parse_number::<u32>(1, input, &input[3..]) {
+                Err(AocError::Parse { line, column, .. }) => Some((line, column)),
+                _ => None,
+            }
+        );
+    }
+
+    #[test]
+    fn offsets_parse_errors() {
+        assert_eq!(
+            AocError::parse(4, 8, 2, "bad"),
+            AocError::parse(4, 1, 2, "bad").offset_lines(7)
+        );
+        assert_eq!(
+            AocError::invalid(4, "bad"),
+            AocError::invalid(4, "bad").offset_lines(7)
+        );
+    }
+
+    #[test]
+    fn describes_errors() {
+        assert_eq!(
+            "day 5, line 3, column 7: expected ' -> '",
+            AocError::parse(5, 3, 7, "expected ' -> '").to_string()
+        );
+        assert_eq!(
+            "day 4: no solution: no board won",
+            AocError::unsolvable(4, "no board won").to_string()
+        );
+    }
+}
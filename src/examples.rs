@@ -0,0 +1,92 @@
+use crate::{
+    day01, day02, day03, day04, day05, day06, day07, day08,
+    solution::{Answer, Part},
+};
+
+/// An example input from a puzzle description, with the answers given there.
+#[derive(Debug, PartialEq)]
+pub struct Example {
+    pub day: u32,
+    pub name: &'static str,
+    pub input: &'static str,
+    /// The expected answers, where the puzzle description gives them.
+    pub answers: [Option<Answer>; 2],
+}
+impl Example {
+    pub fn expected(&self, part: Part) -> Option<Answer> {
+        self.answers[part.number() as usize - 1]
+    }
+}
+
+/// Every example, in calendar order.
+pub const EXAMPLES: &[Example] = &[
+    example(1, day01::EXAMPLE, Some(7), Some(5)),
+    example(2, day02::EXAMPLE, Some(150), Some(900)),
+    example(3, day03::EXAMPLE, Some(198), Some(230)),
+    example(4, day04::EXAMPLE, Some(4512), Some(1924)),
+    example(5, day05::EXAMPLE, Some(5), Some(12)),
+    example(6, day06::EXAMPLE, Some(5934), Some(26984457539)),
+    example(7, day07::EXAMPLE, Some(37), Some(168)),
+    example(8, day08::EXAMPLE, Some(26), Some(61229)),
+    Example {
+        name: "entry",
+        ..example(8, day08::EXAMPLE_ENTRY, Some(0), Some(5353))
+    },
+];
+
+const fn example(
+    day: u32,
+    input: &'static str,
+    part1: Option<Answer>,
+    part2: Option<Answer>,
+) -> Example {
+    Example {
+        day,
+        name: "example",
+        input,
+        answers: [part1, part2],
+    }
+}
+
+/// The examples for one day.
+pub fn for_day(day: u32) -> impl Iterator<Item = &'static Example> {
+    EXAMPLES.iter().filter(move |e| e.day == day)
+}
+
+pub fn find(day: u32, name: &str) -> Option<&'static Example> {
+    for_day(day).find(|e| e.name == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solution::{self, DAYS};
+
+    #[test]
+    fn every_day_has_an_example() {
+        for s in DAYS {
+            assert!(for_day(s.day()).next().is_some(), "day {}", s.day());
+        }
+        assert_eq!(Some(5353), find(8, "entry").and_then(|e| e.answers[1]));
+        assert_eq!(None, find(8, "no such example"));
+    }
+
+    #[test]
+    fn examples_have_expected_answers() {
+        for e in EXAMPLES {
+            let s = solution::find(e.day).unwrap();
+            for &part in &Part::BOTH {
+                if let Some(expected) = e.expected(part) {
+                    assert_eq!(
+                        Ok(expected),
+                        s.solve(part, e.input),
+                        "day {} {} part {}",
+                        e.day,
+                        e.name,
+                        part.number()
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,153 @@
+//! Random puzzle inputs, for testing and benchmarking beyond the embedded inputs.
+//!
+//! Every input is generated from a seed, so the same seed and size always give
+//! the same input.
+
+use std::ops::RangeInclusive;
+
+use crate::{day01, day02, day03, day04, day05, day06, day07, day08};
+
+/// A small, fast and reproducible random number generator (SplitMix64).
+///
+/// This is not suitable for anything which needs to be unpredictable.
+#[derive(Clone, Debug)]
+pub struct Rng(u64);
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..n`, which must not be empty.
+    pub fn below(&mut self, n: u64) -> u64 {
+        assert!(n > 0, "No numbers below zero");
+        ((self.next_u64() as u128 * n as u128) >> 64) as u64
+    }
+
+    pub fn range(&mut self, range: RangeInclusive<u32>) -> u32 {
+        let (start, end) = range.into_inner();
+        start + self.below(u64::from(end - start) + 1) as u32
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i as u64 + 1) as usize);
+        }
+    }
+}
+
+/// Writes random inputs for one day.
+pub struct Generator {
+    pub day: u32,
+    /// What `size` counts, such as "depths".
+    pub unit: &'static str,
+    /// The size of the real puzzle inputs.
+    pub default_size: usize,
+    pub generate: fn(&mut Rng, usize) -> String,
+}
+
+/// Every generator, in calendar order.
+pub const GENERATORS: &[Generator] = &[
+    generator(1, "depths", 2000, day01::generate),
+    generator(2, "instructions", 1000, day02::generate),
+    generator(3, "numbers", 1000, day03::generate),
+    generator(4, "boards", 100, day04::generate),
+    generator(5, "segments", 500, day05::generate),
+    generator(6, "fish", 300, day06::generate),
+    generator(7, "crabs", 1000, day07::generate),
+    generator(8, "entries", 200, day08::generate),
+];
+
+const fn generator(
+    day: u32,
+    unit: &'static str,
+    default_size: usize,
+    generate: fn(&mut Rng, usize) -> String,
+) -> Generator {
+    Generator {
+        day,
+        unit,
+        default_size,
+        generate,
+    }
+}
+
+pub fn find(day: u32) -> Option<&'static Generator> {
+    GENERATORS.iter().find(|g| g.day == day)
+}
+
+/// Generates an input for `day` from `seed`, or `None` if there is no generator for the day.
+pub fn generate(day: u32, size: Option<usize>, seed: u64) -> Option<String> {
+    let g = find(day)?;
+    Some((g.generate)(
+        &mut Rng::new(seed),
+        size.unwrap_or(g.default_size),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solution::{self, Part, DAYS};
+
+    #[test]
+    fn stays_in_range() {
+        let mut rng = Rng::new(7);
+        for _ in 0..1000 {
+            assert!((3..=5).contains(&rng.range(3..=5)));
+            assert!(rng.below(10) < 10);
+        }
+        assert_eq!(0, rng.range(0..=0));
+        let mut items = (0..10).collect::<Vec<_>>();
+        rng.shuffle(&mut items);
+        items.sort_unstable();
+        assert_eq!((0..10).collect::<Vec<_>>(), items);
+    }
+
+    #[test]
+    fn every_day_has_a_generator() {
+        for s in DAYS {
+            assert!(find(s.day()).is_some(), "day {}", s.day());
+        }
+        assert_eq!(None, generate(26, None, 0));
+    }
+
+    #[test]
+    fn generates_reproducibly() {
+        for g in GENERATORS {
+            let input = generate(g.day, Some(20), 1);
+            assert_eq!(input, generate(g.day, Some(20), 1));
+            assert_ne!(input, generate(g.day, Some(20), 2), "day {}", g.day);
+        }
+    }
+
+    #[test]
+    fn generates_solvable_inputs() {
+        for g in GENERATORS {
+            let s = solution::find(g.day).unwrap();
+            for seed in 0..10 {
+                for &size in &[1, 2, 50, g.default_size] {
+                    let input = generate(g.day, Some(size), seed).unwrap();
+                    for &part in &Part::BOTH {
+                        assert!(
+                            s.solve(part, &input).is_ok(),
+                            "day {} part {}, size {}, seed {}: {:?}",
+                            g.day,
+                            part.number(),
+                            size,
+                            seed,
+                            s.solve(part, &input)
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,270 @@
+use std::{
+    borrow::Cow,
+    fmt::Display,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use crate::solution::Solution;
+
+/// The directory searched for `dayNN.txt` inputs when no path is given.
+pub const DEFAULT_DIR: &str = "inputs";
+
+/// Where a puzzle input was read from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Source {
+    File(PathBuf),
+    Stdin,
+    Embedded,
+    /// One of the [examples](crate::examples), by name.
+    Example(&'static str),
+}
+impl Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::File(path) => write!(f, "{}", path.display()),
+            Source::Stdin => f.write_str("stdin"),
+            Source::Embedded => f.write_str("embedded"),
+            Source::Example(name) => write!(f, "example {}", name),
+        }
+    }
+}
+
+pub struct Input {
+    pub text: Cow<'static, str>,
+    pub source: Source,
+}
+impl Input {
+    /// The hash of the normalized text, so that an input is recognised
+    /// however it was edited.
+    pub fn hash(&self) -> u64 {
+        hash(&normalize(&self.text))
+    }
+
+    /// A line of the normalized text, counting from 1 as parse errors do.
+    pub fn line(&self, line: usize) -> Option<String> {
+        let index = line.checked_sub(1)?;
+        normalize(&self.text).lines().nth(index).map(str::to_string)
+    }
+}
+
+/// Removes the differences an editor might make to an input: a UTF-8 byte order
+/// mark, CRLF line endings, trailing whitespace on each line, and trailing blank lines.
+///
+/// The result has `\n` line endings, and ends with a single `\n` unless it is empty.
+/// Every [`Solution`] normalizes its input before parsing.
+pub fn normalize(text: &str) -> Cow<'_, str> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let body = text.trim_end();
+    let is_normal = !text.contains('\r')
+        && text.len() == body.len() + 1
+        && text.ends_with('\n')
+        && body.lines().all(|l| l.len() == l.trim_end().len());
+    if is_normal || text.is_empty() {
+        return Cow::Borrowed(text);
+    }
+    let mut output = String::with_capacity(text.len());
+    for line in body.lines() {
+        output.push_str(line.trim_end());
+        output.push('\n');
+    }
+    Cow::Owned(output)
+}
+
+/// A stable (FNV-1a) hash of the input text, used to recognise an input
+/// without storing it.
+pub fn hash(text: &str) -> u64 {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    text.bytes()
+        .fold(OFFSET, |h, b| (h ^ u64::from(b)).wrapping_mul(PRIME))
+}
+
+/// Finds the puzzle input for a day, in order of preference:
+/// 1. the explicit `path`, where `-` means stdin;
+/// 2. `dayNN.txt` inside `dir`, if it exists;
+/// 3. the input embedded in the binary.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Provider {
+    pub path: Option<PathBuf>,
+    pub dir: PathBuf,
+}
+impl Default for Provider {
+    fn default() -> Self {
+        Self {
+            path: None,
+            dir: PathBuf::from(DEFAULT_DIR),
+        }
+    }
+}
+impl Provider {
+    pub fn load(&self, solution: &dyn Solution) -> Result<Input, String> {
+        match &self.path {
+            Some(path) if path == Path::new("-") => {
+                let mut text = String::new();
+                std::io::stdin()
+                    .read_to_string(&mut text)
+                    .map_err(|e| format!("couldn't read stdin: {}", e))?;
+                Ok(Input {
+                    text: text.into(),
+                    source: Source::Stdin,
+                })
+            }
+            Some(path) => read_file(path),
+            None => {
+                let path = self.dir.join(file_name(solution.day()));
+                if path.is_file() {
+                    read_file(&path)
+                } else {
+                    Ok(Input {
+                        text: solution.input().into(),
+                        source: Source::Embedded,
+                    })
+                }
+            }
+        }
+    }
+}
+
+/// The conventional file name for a day's input.
+pub fn file_name(day: u32) -> String {
+    format!("day{:02}.txt", day)
+}
+
+/// Every file in `dir`, in order of name, for solving a day against many inputs.
+/// Hidden files and subdirectories are skipped.
+pub fn read_dir(dir: &Path) -> Result<Vec<Input>, String> {
+    let entries =
+        std::fs::read_dir(dir).map_err(|e| format!("couldn't read {}: {}", dir.display(), e))?;
+    let mut paths = Vec::new();
+    for entry in entries {
+        let path = entry
+            .map_err(|e| format!("couldn't read {}: {}", dir.display(), e))?
+            .path();
+        let hidden = path
+            .file_name()
+            .is_none_or(|name| name.to_string_lossy().starts_with('.'));
+        if path.is_file() && !hidden {
+            paths.push(path);
+        }
+    }
+    if paths.is_empty() {
+        return Err(format!("there are no inputs in {}", dir.display()));
+    }
+    paths.sort();
+    paths.iter().map(|path| read_file(path)).collect()
+}
+
+fn read_file(path: &Path) -> Result<Input, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("couldn't read {}: {}", path.display(), e))?;
+    Ok(Input {
+        text: text.into(),
+        source: Source::File(path.to_owned()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day06::Day06;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("aoc-input-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn hashes_stably() {
+        assert_eq!(0xcbf2_9ce4_8422_2325, hash(""));
+        assert_eq!(0xaf63_dc4c_8601_ec8c, hash("a"));
+        assert_ne!(hash("3,4,3,1,2"), hash("3,4,3,1,2\n"));
+    }
+
+    #[test]
+    fn normalizes_text() {
+        assert_eq!(
+            "a\n b\n\nc\n",
+            normalize("\u{feff}a \r\n b\t\r\n\r\nc\r\n\r\n  \r\n")
+        );
+        assert_eq!("3,4,3,1,2\n", normalize("3,4,3,1,2"));
+        assert_eq!("", normalize("\n\n"));
+        assert_eq!("", normalize(""));
+        assert!(matches!(normalize("a\nb\n"), Cow::Borrowed("a\nb\n")));
+        for s in crate::solution::DAYS {
+            assert!(matches!(normalize(s.input()), Cow::Borrowed(_)));
+        }
+    }
+
+    #[test]
+    fn names_files_by_day() {
+        assert_eq!("day06.txt", file_name(6));
+        assert_eq!("day25.txt", file_name(25));
+    }
+
+    #[test]
+    fn prefers_directory_over_embedded() {
+        let dir = scratch_dir("dir");
+        std::fs::write(dir.join("day06.txt"), "3,4,3,1,2\n").unwrap();
+        let provider = Provider {
+            path: None,
+            dir: dir.clone(),
+        };
+        let input = provider.load(&Day06).unwrap();
+        assert_eq!("3,4,3,1,2\n", input.text);
+        assert_eq!(Source::File(dir.join("day06.txt")), input.source);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn falls_back_to_embedded() {
+        let provider = Provider {
+            path: None,
+            dir: scratch_dir("empty"),
+        };
+        let input = provider.load(&Day06).unwrap();
+        assert_eq!(Source::Embedded, input.source);
+        assert_eq!(crate::day06::INPUT, input.text);
+    }
+
+    #[test]
+    fn reads_every_file_in_order() {
+        let dir = scratch_dir("batch");
+        std::fs::write(dir.join("bob.txt"), "1\n").unwrap();
+        std::fs::write(dir.join("alice.txt"), "2\n").unwrap();
+        std::fs::write(dir.join(".hidden"), "3\n").unwrap();
+        std::fs::create_dir_all(dir.join("old")).unwrap();
+        let inputs = read_dir(&dir).unwrap();
+        assert_eq!(
+            vec![
+                Source::File(dir.join("alice.txt")),
+                Source::File(dir.join("bob.txt"))
+            ],
+            inputs.into_iter().map(|i| i.source).collect::<Vec<_>>()
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(read_dir(&dir).is_err());
+        assert!(read_dir(&scratch_dir("no-inputs")).is_err());
+    }
+
+    #[test]
+    fn finds_lines() {
+        let input = Input {
+            text: "a\r\nb  \r\n".into(),
+            source: Source::Stdin,
+        };
+        assert_eq!(Some("b".to_string()), input.line(2));
+        assert_eq!(None, input.line(0));
+        assert_eq!(None, input.line(3));
+    }
+
+    #[test]
+    fn reports_missing_file() {
+        let provider = Provider {
+            path: Some(PathBuf::from("no/such/input.txt")),
+            ..Default::default()
+        };
+        assert!(provider.load(&Day06).is_err());
+    }
+}
@@ -0,0 +1,31 @@
+//! Solutions to Advent of Code 2021.
+//!
+//! Each `dayNN` module has a [`Puzzle`] which parses the input into a model and
+//! answers both parts from it, along with the model types and the solvers they use.
+//! Every day is listed in [`DAYS`].
+
+pub mod bench;
+pub mod cache;
+pub mod crosscheck;
+pub mod day01;
+pub mod day02;
+pub mod day03;
+pub mod day04;
+pub mod day05;
+pub mod day06;
+pub mod day07;
+pub mod day08;
+pub mod error;
+pub mod examples;
+pub mod generate;
+pub mod input;
+pub mod memory;
+pub mod parallel;
+pub mod solution;
+pub mod store;
+pub mod stream;
+pub mod trace;
+pub mod verify;
+
+pub use error::AocError;
+pub use solution::{find, Answer, Part, Puzzle, Solution, DAYS};
@@ -1,28 +1,869 @@
-use std::io::stdin;
+use std::{path::Path, process::exit, time::Duration};
 
-mod day01;
-mod day02;
-mod day03;
-mod day04;
-mod day05;
-mod day06;
-mod day07;
-mod day08;
+use cli::{
+    Bench, Command, Crosscheck, Dive, Examples, Generate, Report, Run, Selection, Serve, Verify,
+};
+use output::Format;
+use rust_aoc_2021::{
+    bench::{format_change, measure, Baseline, Phase},
+    cache::{self, Cache},
+    crosscheck, day01, day02,
+    examples::{self, Example},
+    generate,
+    input::{self, Input, Source},
+    memory, parallel, solution,
+    solution::{PartTiming, Timings},
+    trace, verify, Answer, AocError, Part, Solution,
+};
+use table::{format_bytes, format_duration, Align, Table};
+
+mod cli;
+mod output;
+mod serve;
+mod table;
+
+/// Counts allocations, but only for `run --memory`.
+#[global_allocator]
+static ALLOCATOR: memory::Counting = memory::Counting;
 
 fn main() {
-    println!("What day?");
-    let mut input = String::new();
-    let _ = stdin().read_line(&mut input).expect("Couldn't read stdin");
-    let day = input.trim().parse::<u32>().expect("Input was not a number.");
-    match day {
-        1 => println!("Day 1: {}, {}", day01::part1(), day01::part2()),
-        2 => println!("Day 2: {}, {}", day02::part1(), day02::part2()),
-        3 => println!("Day 3: {}, {}", day03::part1(day03::INPUT), day03::part2(day03::INPUT)),
-        4 => println!("Day 4: {}, {}", day04::part1(day04::INPUT), day04::part2(day04::INPUT)),
-        5 => println!("Day 5: {}, {}", day05::part1(day05::INPUT), day05::part2(day05::INPUT)),
-        6 => println!("Day 6: {}, {}", day06::part1(day06::INPUT), day06::part2(day06::INPUT)),
-        7 => println!("Day 7: {}, {}", day07::part1(day07::INPUT), day07::part2(day07::INPUT)),
-        8 => println!("Day 8: {}, {}", day08::part1(day08::INPUT), day08::part2(day08::INPUT)),
-        _ => println!("NOPE."),
+    let result = cli::parse(std::env::args().skip(1))
+        .map_err(Error::Usage)
+        .and_then(command);
+    exit(exit_code(result));
+}
+
+/// Why a command failed.
+enum Error {
+    /// The command line could not be understood.
+    Usage(cli::UsageError),
+    Message(String),
+    /// The command ran, but found answers which were wrong, and has said so.
+    Failed,
+}
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Error::Message(message)
+    }
+}
+
+/// Prints the error, if there is one, and returns the process's exit code.
+fn exit_code(result: Result<(), Error>) -> i32 {
+    match result {
+        Ok(()) => 0,
+        Err(Error::Usage(e)) => {
+            eprintln!("error: {}\n\n{}", e, cli::USAGE);
+            2
+        }
+        Err(Error::Message(e)) => {
+            eprintln!("error: {}", e);
+            1
+        }
+        Err(Error::Failed) => 1,
+    }
+}
+
+fn command(command: Command) -> Result<(), Error> {
+    match command {
+        Command::Run(run) => execute(run),
+        Command::Verify(v) => verify(v),
+        Command::Bench(b) => bench(b),
+        Command::Crosscheck(c) => crosscheck(c),
+        Command::Generate(g) => generate(g),
+        Command::Serve(s) => serve(s),
+        Command::Report(r) => report(r),
+        Command::Dive(d) => dive(d),
+        Command::ClearCache => {
+            let count = Cache::clear(Path::new(cache::DEFAULT_PATH))?;
+            println!("Removed {} cached answers", count);
+            Ok(())
+        }
+        Command::List => {
+            for s in solution::DAYS {
+                println!("{:>3}: {}", s.day(), s.name());
+            }
+            Ok(())
+        }
+        Command::Help => {
+            println!("{}", cli::USAGE);
+            Ok(())
+        }
+    }
+}
+
+/// A day's input, and the outcome of solving it.
+struct Solved {
+    solution: &'static dyn Solution,
+    source: Source,
+    timings: Result<Timings, AocError>,
+    /// The example which was solved, rather than a puzzle input.
+    example: Option<&'static Example>,
+    /// The answers were cached by an earlier run, so there are no times.
+    cached: bool,
+}
+
+fn execute(run: Run) -> Result<(), Error> {
+    let parts = run
+        .part
+        .as_ref()
+        .map_or(&Part::BOTH[..], std::slice::from_ref);
+    let days = match run.days {
+        Selection::Day(day) => vec![find(day)?],
+        Selection::All => solution::DAYS.to_vec(),
+    };
+    let inputs = match (&run.examples, &run.batch) {
+        (Some(choice), _) => examples_for(&days, choice)?,
+        (None, Some(dir)) => input::read_dir(dir)?
+            .into_iter()
+            .map(|input| (days[0], input, None))
+            .collect(),
+        (None, None) => days
+            .into_iter()
+            .map(|s| Ok((s, run.input.load(s)?, None)))
+            .collect::<Result<Vec<_>, String>>()?,
+    };
+    // examples are quick to solve, and tracing and counting need the solvers to run.
+    let use_cache = run.cache && run.examples.is_none() && !run.trace && !run.memory;
+    let cache_path = Path::new(cache::DEFAULT_PATH);
+    let mut cache = match use_cache {
+        true => Cache::load(cache_path).unwrap_or_else(|e| {
+            eprintln!("warning: {}; ignoring the cache", e);
+            Cache::default()
+        }),
+        false => Cache::default(),
+    };
+    let workers = run.jobs.unwrap_or_else(parallel::default_workers);
+    let solved = parallel::map(&inputs, workers, |(s, input, example)| {
+        let solve = || match run.memory {
+            true => memory::counting(|| s.timed(&input.text, parts)),
+            false => s.timed(&input.text, parts),
+        };
+        let cached = match use_cache {
+            true => {
+                let hash = input.hash();
+                parts
+                    .iter()
+                    .map(|&part| cache.get(s.day(), hash, s.version(), part))
+                    .collect::<Option<Vec<_>>>()
+            }
+            false => None,
+        };
+        let timings = if let Some(answers) = &cached {
+            Ok(Timings {
+                parse: Duration::ZERO,
+                parse_memory: None,
+                parts: parts
+                    .iter()
+                    .zip(answers)
+                    .map(|(&part, &answer)| PartTiming {
+                        part,
+                        answer: Ok(answer),
+                        elapsed: Duration::ZERO,
+                        memory: None,
+                    })
+                    .collect(),
+            })
+        } else if run.trace {
+            // print each day's steps together, in order, rather than as they happen.
+            let (timings, events) = trace::capture(solve);
+            for e in events {
+                eprintln!("day {}: {}", s.day(), e);
+            }
+            timings
+        } else {
+            solve()
+        };
+        Solved {
+            solution: *s,
+            timings,
+            source: input.source.clone(),
+            example: *example,
+            cached: cached.is_some(),
+        }
+    });
+    if use_cache {
+        let previous = cache.clone();
+        for (solved, (s, input, _)) in solved.iter().zip(&inputs) {
+            if let (Ok(timings), false) = (&solved.timings, solved.cached) {
+                for p in &timings.parts {
+                    if let Ok(answer) = p.answer {
+                        cache.insert(s.day(), input.hash(), s.version(), p.part, answer);
+                    }
+                }
+            }
+        }
+        if cache != previous {
+            if let Err(e) = cache.save(cache_path) {
+                eprintln!("warning: {}", e);
+            }
+        }
+    }
+    let batch = run.batch.is_some();
+    let errors = solved
+        .iter()
+        .flat_map(|s| {
+            let errors = match &s.timings {
+                Ok(timings) => timings
+                    .parts
+                    .iter()
+                    .filter_map(|p| p.answer.as_ref().err())
+                    .collect(),
+                Err(e) => vec![e],
+            };
+            // every input is for the same day, so name the file instead.
+            errors.into_iter().map(move |e| match batch {
+                true => format!("{}: {}", s.source, e),
+                false => e.to_string(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    match (run.format, run.days) {
+        (Format::Json, _) => print!("{}", output::json(&rows(&solved, parts))),
+        (Format::Csv, _) => print!("{}", output::csv(&rows(&solved, parts))),
+        (Format::Text, _) if run.examples.is_some() => {
+            let failed = print_examples(&solved);
+            if errors.is_empty() && failed > 0 {
+                return Err(format!("{} examples gave unexpected answers", failed).into());
+            }
+        }
+        (Format::Text, _) if batch => print_batch(&solved, &inputs),
+        (Format::Text, Selection::Day(_)) => {
+            if errors.is_empty() {
+                print_answers(&solved[0], run.part)
+            }
+        }
+        (Format::Text, Selection::All) => print_table(&solved, run.memory),
+    }
+    match errors.as_slice() {
+        [] => Ok(()),
+        [e] => Err(e.clone().into()),
+        _ => {
+            for e in &errors {
+                eprintln!("error: {}", e);
+            }
+            Err(format!("{} errors while solving", errors.len()).into())
+        }
+    }
+}
+
+/// A day to solve, its input, and the example that input came from.
+type Job = (&'static dyn Solution, Input, Option<&'static Example>);
+
+/// The examples of each day, as inputs to solve.
+fn examples_for(days: &[&'static dyn Solution], choice: &Examples) -> Result<Vec<Job>, String> {
+    let mut output = Vec::new();
+    for &s in days {
+        for e in examples::for_day(s.day()) {
+            if let Examples::Named(name) = choice {
+                if e.name != name {
+                    continue;
+                }
+            }
+            let input = Input {
+                text: e.input.into(),
+                source: Source::Example(e.name),
+            };
+            output.push((s, input, Some(e)));
+        }
+    }
+    match choice {
+        Examples::Named(name) if output.is_empty() => {
+            Err(format!("there are no examples named '{}'", name))
+        }
+        _ => Ok(output),
+    }
+}
+
+/// Prints each answer to an example alongside the expected answer,
+/// returning how many differed.
+fn print_examples(solved: &[Solved]) -> usize {
+    let mut table = Table::new(&[
+        ("Day", Align::Right),
+        ("Example", Align::Left),
+        ("Part", Align::Right),
+        ("Answer", Align::Right),
+        ("Expected", Align::Right),
+        ("Status", Align::Left),
+    ]);
+    let mut failed = 0;
+    for Solved {
+        solution: s,
+        timings,
+        example,
+        ..
+    } in solved
+    {
+        let example = example.expect("only examples are printed");
+        let answers = match timings {
+            Ok(timings) => timings
+                .parts
+                .iter()
+                .map(|p| (p.part, p.answer.as_ref().ok().copied()))
+                .collect(),
+            Err(_) => Part::BOTH.iter().map(|&p| (p, None)).collect::<Vec<_>>(),
+        };
+        for (part, answer) in answers {
+            let expected = example.expected(part);
+            let status = match (answer, expected) {
+                (_, None) => "-",
+                (Some(a), Some(e)) if a == e => "PASS",
+                _ => {
+                    failed += 1;
+                    "FAIL"
+                }
+            };
+            table.row(vec![
+                s.day().to_string(),
+                example.name.to_string(),
+                part.number().to_string(),
+                answer.map_or_else(|| "ERROR".to_string(), |a| a.to_string()),
+                expected.map_or_else(|| "-".to_string(), |a| a.to_string()),
+                status.to_string(),
+            ]);
+        }
+    }
+    print!("{}", table);
+    failed
+}
+
+/// Prints `Day N: a, b`, for a day which was solved without error.
+fn print_answers(solved: &Solved, part: Option<Part>) {
+    let s = solved.solution;
+    let answers = match &solved.timings {
+        Ok(timings) => timings
+            .parts
+            .iter()
+            .filter_map(|p| p.answer.as_ref().ok())
+            .map(|a| a.to_string())
+            .collect::<Vec<_>>(),
+        Err(_) => return,
+    };
+    let source = match &solved.source {
+        Source::Embedded => String::new(),
+        other => format!(" ({})", other),
+    };
+    match part {
+        Some(p) => println!(
+            "Day {} part {}: {}{}",
+            s.day(),
+            p.number(),
+            answers[0],
+            source
+        ),
+        None => println!("Day {}: {}{}", s.day(), answers.join(", "), source),
+    }
+}
+
+/// Prints every day's answers and times, and with `memory`, the allocations
+/// of each phase beneath.
+fn print_table(solved: &[Solved], memory: bool) {
+    let mut headings = vec![
+        ("Day", Align::Right),
+        ("Puzzle", Align::Left),
+        ("Part 1", Align::Right),
+        ("Part 2", Align::Right),
+        ("Parse", Align::Right),
+        ("Solve 1", Align::Right),
+        ("Solve 2", Align::Right),
+        ("Total", Align::Right),
+    ];
+    if memory {
+        headings.extend(vec![
+            ("Allocs", Align::Right),
+            ("Allocated", Align::Right),
+            ("Peak", Align::Right),
+        ]);
+    }
+    let mut table = Table::new(&headings);
+    // parse, part 1, part 2, overall
+    let mut totals = [Duration::ZERO; 4];
+    for Solved {
+        solution: s,
+        timings,
+        cached,
+        ..
+    } in solved
+    {
+        let timings = match timings {
+            Ok(timings) => timings,
+            Err(_) => {
+                let failed = || "ERROR".to_string();
+                table.row(vec![
+                    s.day().to_string(),
+                    s.name().to_string(),
+                    failed(),
+                    failed(),
+                ]);
+                continue;
+            }
+        };
+        let mut answers = [String::new(), String::new()];
+        let mut elapsed = ["-".to_string(), "-".to_string()];
+        for p in timings.parts.iter() {
+            let index = p.part.number() as usize - 1;
+            answers[index] = match &p.answer {
+                Ok(answer) => answer.to_string(),
+                Err(_) => "ERROR".to_string(),
+            };
+            elapsed[index] = format_time(p.elapsed, *cached);
+            totals[index + 1] += p.elapsed;
+        }
+        totals[0] += timings.parse;
+        totals[3] += timings.total();
+
+        let [answer1, answer2] = answers;
+        let [elapsed1, elapsed2] = elapsed;
+        table.row(vec![
+            s.day().to_string(),
+            s.name().to_string(),
+            answer1,
+            answer2,
+            format_time(timings.parse, *cached),
+            elapsed1,
+            elapsed2,
+            format_total(timings, *cached),
+        ]);
+        let phases = std::iter::once((Phase::Parse, timings.parse_memory)).chain(
+            timings
+                .parts
+                .iter()
+                .map(|p| (Phase::Part(p.part), p.memory)),
+        );
+        for (phase, usage) in phases {
+            if let Some(usage) = usage {
+                let mut row = vec![String::new(), format!("  {}", phase)];
+                row.resize(8, String::new());
+                row.extend(vec![
+                    usage.allocations.to_string(),
+                    format_bytes(usage.bytes),
+                    format_bytes(usage.peak),
+                ]);
+                table.row(row);
+            }
+        }
+    }
+    table.rule();
+    let mut footer = vec![
+        String::new(),
+        "Total".to_string(),
+        String::new(),
+        String::new(),
+    ];
+    footer.extend(totals.iter().map(|&d| format_duration(d)));
+    table.row(footer);
+    print!("{}", table);
+}
+
+/// Prints the answers and times for each file, flagging the line of any
+/// file which can't be parsed.
+fn print_batch(solved: &[Solved], inputs: &[Job]) {
+    let mut table = Table::new(&[
+        ("File", Align::Left),
+        ("Part 1", Align::Right),
+        ("Part 2", Align::Right),
+        ("Parse", Align::Right),
+        ("Solve 1", Align::Right),
+        ("Solve 2", Align::Right),
+        ("Total", Align::Right),
+        ("Problem", Align::Left),
+    ]);
+    for (solved, (_, input, _)) in solved.iter().zip(inputs) {
+        let file = match &solved.source {
+            Source::File(path) => path.file_name().map_or_else(
+                || path.display().to_string(),
+                |n| n.to_string_lossy().into(),
+            ),
+            other => other.to_string(),
+        };
+        let timings = match &solved.timings {
+            Ok(timings) => timings,
+            Err(e) => {
+                let problem = match e {
+                    AocError::Parse { line, message, .. } => match input.line(*line) {
+                        Some(text) => format!("line {}: {:?}: {}", line, excerpt(&text), message),
+                        None => format!("line {}: {}", line, message),
+                    },
+                    other => other.to_string(),
+                };
+                let failed = || "ERROR".to_string();
+                let blank = String::new;
+                table.row(vec![
+                    file,
+                    failed(),
+                    failed(),
+                    blank(),
+                    blank(),
+                    blank(),
+                    blank(),
+                    problem,
+                ]);
+                continue;
+            }
+        };
+        let mut answers = [String::new(), String::new()];
+        let mut elapsed = ["-".to_string(), "-".to_string()];
+        let mut problems = Vec::new();
+        for p in timings.parts.iter() {
+            let index = p.part.number() as usize - 1;
+            answers[index] = match &p.answer {
+                Ok(answer) => answer.to_string(),
+                Err(e) => {
+                    problems.push(format!("part {}: {}", p.part.number(), e));
+                    "ERROR".to_string()
+                }
+            };
+            elapsed[index] = format_time(p.elapsed, solved.cached);
+        }
+        let [answer1, answer2] = answers;
+        let [elapsed1, elapsed2] = elapsed;
+        table.row(vec![
+            file,
+            answer1,
+            answer2,
+            format_time(timings.parse, solved.cached),
+            elapsed1,
+            elapsed2,
+            format_total(timings, solved.cached),
+            problems.join("; "),
+        ]);
+    }
+    print!("{}", table);
+}
+
+/// A time for the table, or `-` if the answers were cached and nothing was timed.
+fn format_time(elapsed: Duration, cached: bool) -> String {
+    match cached {
+        true => "-".to_string(),
+        false => format_duration(elapsed),
+    }
+}
+
+fn format_total(timings: &Timings, cached: bool) -> String {
+    match cached {
+        true => "cached".to_string(),
+        false => format_duration(timings.total()),
+    }
+}
+
+/// The start of a line, short enough to fit in a table.
+fn excerpt(line: &str) -> String {
+    const LENGTH: usize = 30;
+    match line.char_indices().nth(LENGTH) {
+        Some((end, _)) => format!("{}...", &line[..end]),
+        None => line.to_string(),
+    }
+}
+
+/// One row per part of every day, for machine-readable output.
+fn rows(solved: &[Solved], parts: &[Part]) -> Vec<output::Row> {
+    let mut rows = Vec::new();
+    for Solved {
+        solution: s,
+        source,
+        timings,
+        cached,
+        ..
+    } in solved
+    {
+        let row = |part, answer, parse, solve| output::Row {
+            day: s.day(),
+            name: s.name(),
+            part,
+            answer,
+            parse,
+            solve,
+            source: source.clone(),
+        };
+        match timings {
+            Ok(timings) => rows.extend(timings.parts.iter().map(|p| {
+                row(
+                    p.part,
+                    p.answer.clone().map_err(|e| e.to_string()),
+                    (!cached).then_some(timings.parse),
+                    (!cached).then_some(p.elapsed),
+                )
+            })),
+            Err(e) => rows.extend(
+                parts
+                    .iter()
+                    .map(|&p| row(p, Err(e.to_string()), None, None)),
+            ),
+        }
+    }
+    rows
+}
+
+/// Checks answers against those recorded, returning `false` if any differ.
+fn verify(v: Verify) -> Result<(), Error> {
+    let days = match v.days {
+        Selection::Day(day) => vec![find(day)?],
+        Selection::All => solution::DAYS.to_vec(),
+    };
+    let mut answers = verify::Answers::load(&v.answers)?;
+    let workers = v.jobs.unwrap_or_else(parallel::default_workers);
+    let checks = verify::verify(&days, &v.input, &answers, workers)?;
+
+    let mut table = Table::new(&[
+        ("Day", Align::Right),
+        ("Part", Align::Right),
+        ("Answer", Align::Right),
+        ("Recorded", Align::Right),
+        ("Status", Align::Left),
+    ]);
+    for c in &checks {
+        table.row(vec![
+            c.day.to_string(),
+            c.part.number().to_string(),
+            c.answer
+                .as_ref()
+                .map_or_else(|_| "ERROR".to_string(), |a| a.to_string()),
+            c.expected
+                .map_or_else(|| "-".to_string(), |a| a.to_string()),
+            c.status.to_string(),
+        ]);
+    }
+    print!("{}", table);
+    for e in checks.iter().filter_map(|c| c.answer.as_ref().err()) {
+        eprintln!("error: {}", e);
+    }
+
+    let count = |status| checks.iter().filter(|c| c.status == status).count();
+    let (passed, failed, changed) = (
+        count(verify::Status::Pass),
+        count(verify::Status::Fail),
+        count(verify::Status::Changed),
+    );
+    println!("{} passed, {} failed, {} changed", passed, failed, changed);
+    if v.record && changed > 0 {
+        for c in checks
+            .iter()
+            .filter(|c| c.status == verify::Status::Changed)
+        {
+            if let Ok(answer) = c.answer {
+                answers.insert(c.day, c.hash, c.part, answer);
+            }
+        }
+        answers.save(&v.answers)?;
+        println!("Recorded {} answers in {}", changed, v.answers.display());
+    }
+    match failed {
+        0 => Ok(()),
+        _ => Err(Error::Failed),
+    }
+}
+
+/// Times every phase of each day, optionally comparing with and saving a baseline.
+fn bench(b: Bench) -> Result<(), Error> {
+    let days = match b.days {
+        Selection::Day(day) => vec![find(day)?],
+        Selection::All => solution::DAYS.to_vec(),
+    };
+    let previous = match &b.baseline {
+        Some(path) => Some(Baseline::load(path)?),
+        None => None,
+    };
+    let mut headings = vec![
+        ("Day", Align::Right),
+        ("Puzzle", Align::Left),
+        ("Phase", Align::Left),
+        ("Min", Align::Right),
+        ("Median", Align::Right),
+        ("p95", Align::Right),
+        ("Throughput", Align::Right),
+    ];
+    if previous.is_some() {
+        headings.push(("Change", Align::Right));
+    }
+    let mut table = Table::new(&headings);
+    let mut baseline = match &b.save {
+        Some(path) => Baseline::load(path)?,
+        None => Baseline::default(),
     };
+    let measured = parallel::map(&days, b.jobs, |&s| -> Result<_, String> {
+        let input = b.input.load(s)?;
+        Ok(measure(s, &input.text, b.warmup, b.runs))
+    });
+    let mut errors = Vec::new();
+    for (i, (s, m)) in days.into_iter().zip(measured).enumerate() {
+        if i > 0 {
+            table.rule();
+        }
+        let m = match m? {
+            Ok(m) => m,
+            Err(e) => {
+                table.row(vec![
+                    s.day().to_string(),
+                    s.name().to_string(),
+                    "ERROR".to_string(),
+                ]);
+                errors.push(e);
+                continue;
+            }
+        };
+        for (phase, stats) in &m.phases {
+            let mut row = vec![
+                s.day().to_string(),
+                s.name().to_string(),
+                phase.to_string(),
+                format_duration(stats.min),
+                format_duration(stats.median),
+                format_duration(stats.p95),
+                match phase {
+                    Phase::Total => format!("{:.1} MB/s", m.throughput() / 1e6),
+                    _ => String::new(),
+                },
+            ];
+            if let Some(previous) = &previous {
+                row.push(
+                    previous
+                        .get(m.day, m.hash, *phase)
+                        .map_or_else(|| "-".to_string(), |d| format_change(d, stats.median)),
+                );
+            }
+            table.row(row);
+        }
+        baseline.insert(&m);
+    }
+    println!("{} timed runs after {} warm-up runs", b.runs, b.warmup);
+    print!("{}", table);
+    if let Some(path) = &b.save {
+        baseline.save(path)?;
+        println!("Saved median times in {}", path.display());
+    }
+    match errors.as_slice() {
+        [] => Ok(()),
+        [e] => Err(e.to_string().into()),
+        _ => {
+            for e in &errors {
+                eprintln!("error: {}", e);
+            }
+            Err(format!("{} errors while solving", errors.len()).into())
+        }
+    }
+}
+
+fn generate(g: Generate) -> Result<(), Error> {
+    let generator = generate::find(g.day)
+        .ok_or_else(|| format!("day {} has no generator; see `list`", g.day))?;
+    let seed = g.seed.unwrap_or_else(new_seed);
+    let size = g.size.unwrap_or(generator.default_size);
+    // on stderr, like the seed, so that the input can be redirected to a file.
+    eprintln!("day {}: {} {}", g.day, size, generator.unit);
+    print!(
+        "{}",
+        (generator.generate)(&mut generate::Rng::new(seed), size)
+    );
+    Ok(())
+}
+
+fn serve(s: Serve) -> Result<(), Error> {
+    let listener = std::net::TcpListener::bind(("127.0.0.1", s.port))
+        .map_err(|e| format!("couldn't listen on port {}: {}", s.port, e))?;
+    eprintln!(
+        "Listening on http://{}; POST an input to /day/N/part/P",
+        listener.local_addr().map_err(|e| e.to_string())?
+    );
+    Ok(serve::serve(listener)?)
+}
+
+/// Analyses the depths of a day 1 input, and draws them.
+fn report(r: Report) -> Result<(), Error> {
+    let input = r.input.load(&day01::Day01)?;
+    let depths = day01::parse_depths(&input::normalize(&input.text)).map_err(|e| e.to_string())?;
+    print!("{}", day01::report::report(&depths, r.window));
+    let chart = day01::report::chart(&depths, r.width, r.height);
+    if !chart.is_empty() {
+        print!("\n{}", chart);
+    }
+    Ok(())
+}
+
+/// Follows a day 2 input with the chosen model, and prints where the submarine ends up.
+fn dive(d: Dive) -> Result<(), Error> {
+    let model =
+        day02::model::find(&d.model).ok_or_else(|| format!("no model named '{}'", d.model))?;
+    let input = d.input.load(&day02::Day02)?;
+    let instructions =
+        day02::parse_instructions(&input::normalize(&input.text)).map_err(|e| e.to_string())?;
+    let position = (model.dive)(&instructions, d.surface).map_err(|e| e.to_string())?;
+    println!("Model:    {} ({})", model.name, model.description);
+    println!("Position: {} forward, {} deep", position.x, position.depth);
+    println!(
+        "Answer:   {}",
+        position.answer().map_err(|e| e.to_string())?
+    );
+    Ok(())
+}
+
+/// A seed for generated inputs, which is printed so that they can be generated again.
+fn new_seed() -> u64 {
+    let seed = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64);
+    eprintln!("seed: {}", seed);
+    seed
+}
+
+/// Checks each day's puzzle input, examples and generated inputs in turn,
+/// stopping at the first on which the solver and reference disagree.
+fn crosscheck(c: Crosscheck) -> Result<(), Error> {
+    let days = match c.days {
+        Selection::Day(day) => vec![find(day)?],
+        Selection::All => solution::DAYS.to_vec(),
+    };
+    let seed = c.seed.unwrap_or_else(new_seed);
+    let mut agreed = true;
+    for s in days {
+        let day = s.day();
+        let input = c.input.load(s)?;
+        let mut inputs = vec![(format!("the puzzle input ({})", input.source), input.text)];
+        inputs.extend(
+            examples::for_day(day).map(|e| (Source::Example(e.name).to_string(), e.input.into())),
+        );
+        let generated = crosscheck::cases(seed, c.cases, c.size).filter_map(|case| {
+            let input = case.input(day)?;
+            Some((
+                format!("the input from `{}`", case.command(day)),
+                input.into(),
+            ))
+        });
+
+        let mut checked = 0;
+        let mismatch = inputs
+            .into_iter()
+            .chain(generated)
+            .find_map(|(name, text)| {
+                checked += 1;
+                let mismatches = crosscheck::compare(s, &text);
+                (!mismatches.is_empty()).then_some((name, mismatches))
+            });
+        match mismatch {
+            None => println!("Day {}: {} inputs agree", day, checked),
+            Some((name, mismatches)) => {
+                agreed = false;
+                println!("Day {}: the solver and reference disagree on {}", day, name);
+                let show = |answer: &Result<Answer, AocError>| match answer {
+                    Ok(answer) => answer.to_string(),
+                    Err(e) => format!("error ({})", e),
+                };
+                for m in mismatches {
+                    println!(
+                        "  part {}: solver gave {}, reference gave {}",
+                        m.part.number(),
+                        show(&m.solver),
+                        show(&m.reference)
+                    );
+                }
+            }
+        }
+    }
+    match agreed {
+        true => Ok(()),
+        false => Err(Error::Failed),
+    }
+}
+
+fn find(day: u32) -> Result<&'static dyn Solution, String> {
+    solution::find(day).ok_or_else(|| format!("day {} has no solution; see `list`", day))
 }
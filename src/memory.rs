@@ -0,0 +1,213 @@
+//! Counting the memory the solvers allocate.
+//!
+//! [`Counting`] is a global allocator which counts each thread's allocations
+//! while [`counting`] runs there, and [`measure`] reports those of one step.
+//! Nothing is counted unless it is installed:
+//!
+//! ```ignore
+//! #[global_allocator]
+//! static ALLOCATOR: rust_aoc_2021::memory::Counting = rust_aoc_2021::memory::Counting;
+//! ```
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+/// The memory allocated by one step.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Usage {
+    /// How many times memory was allocated or grown.
+    pub allocations: u64,
+    /// The bytes allocated, counting only the growth of reallocations.
+    pub bytes: u64,
+    /// The most bytes live at once, above those live when the step started.
+    pub peak: u64,
+}
+
+/// The allocations of one thread so far.
+#[derive(Clone, Copy)]
+struct Counts {
+    enabled: bool,
+    allocations: u64,
+    bytes: u64,
+    /// Negative if memory allocated before counting has been freed.
+    live: i64,
+    peak: i64,
+}
+
+thread_local! {
+    // no destructor and no allocation, so the allocator can use it safely.
+    static COUNTS: Cell<Counts> = const {
+        Cell::new(Counts {
+            enabled: false,
+            allocations: 0,
+            bytes: 0,
+            live: 0,
+            peak: 0,
+        })
+    };
+}
+
+/// Records an allocation of `grown` bytes, or a free if it is negative.
+fn record(allocated: bool, grown: i64) {
+    let _ = COUNTS.try_with(|c| {
+        let mut counts = c.get();
+        if !counts.enabled {
+            return;
+        }
+        if allocated {
+            counts.allocations += 1;
+            counts.bytes += grown.max(0) as u64;
+        }
+        counts.live += grown;
+        counts.peak = counts.peak.max(counts.live);
+        c.set(counts);
+    });
+}
+
+/// The system allocator, counting what it does on threads which asked.
+pub struct Counting;
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            record(true, layout.size() as i64);
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            record(true, layout.size() as i64);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        record(false, -(layout.size() as i64));
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new = System.realloc(ptr, layout, new_size);
+        if !new.is_null() {
+            record(true, new_size as i64 - layout.size() as i64);
+        }
+        new
+    }
+}
+
+/// Runs `f`, counting the allocations it makes on this thread.
+pub fn counting<T>(f: impl FnOnce() -> T) -> T {
+    /// Puts back whether the thread was counting before, even if `f` panics.
+    struct Restore(bool);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let enabled = self.0;
+            COUNTS.with(|c| c.set(Counts { enabled, ..c.get() }));
+        }
+    }
+    let _restore = Restore(COUNTS.with(|c| {
+        let previous = c.get();
+        c.set(Counts {
+            enabled: true,
+            ..previous
+        });
+        previous.enabled
+    }));
+    f()
+}
+
+/// Runs `f`, returning the memory it allocated, or `None` if this thread
+/// isn't [`counting`].
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, Option<Usage>) {
+    let start = COUNTS.with(Cell::get);
+    if !start.enabled {
+        return (f(), None);
+    }
+    // track the peak of this step alone, then put back the outer peak.
+    COUNTS.with(|c| {
+        c.set(Counts {
+            peak: start.live,
+            ..start
+        })
+    });
+    let output = f();
+    let end = COUNTS.with(Cell::get);
+    COUNTS.with(|c| {
+        c.set(Counts {
+            peak: end.peak.max(start.peak),
+            ..end
+        })
+    });
+    let usage = Usage {
+        allocations: end.allocations - start.allocations,
+        bytes: end.bytes - start.bytes,
+        peak: (end.peak - start.live).max(0) as u64,
+    };
+    (output, Some(usage))
+}
+
+#[cfg(test)]
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_only_when_asked() {
+        let (v, usage) = measure(|| vec![0u8; 1000]);
+        assert_eq!((1000, None), (v.len(), usage));
+
+        let (v, usage) = counting(|| measure(|| vec![0u8; 1000]));
+        assert_eq!(1000, v.len());
+        assert_eq!(
+            Some(Usage {
+                allocations: 1,
+                bytes: 1000,
+                peak: 1000
+            }),
+            usage
+        );
+        assert!(!COUNTS.with(Cell::get).enabled);
+    }
+
+    #[test]
+    fn counts_growth_and_peak() {
+        let (_, usage) = counting(|| {
+            measure(|| {
+                let mut v = Vec::<u8>::with_capacity(100);
+                v.reserve_exact(300);
+                drop(v);
+                drop(vec![0u8; 10]);
+            })
+        });
+        assert_eq!(
+            Some(Usage {
+                allocations: 3,
+                bytes: 310,
+                peak: 300
+            }),
+            usage
+        );
+    }
+
+    #[test]
+    fn nests_steps() {
+        counting(|| {
+            let (_, outer) = measure(|| {
+                let big = vec![0u8; 5000];
+                drop(big);
+                let (_, inner) = measure(|| vec![0u8; 10]);
+                assert_eq!(Some(10), inner.map(|u| u.peak));
+            });
+            assert_eq!(Some(5000), outer.map(|u| u.peak));
+            assert_eq!(Some(2), outer.map(|u| u.allocations));
+        });
+    }
+}
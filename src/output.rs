@@ -0,0 +1,178 @@
+use std::{fmt::Write, time::Duration};
+
+use rust_aoc_2021::{input::Source, Answer, Part};
+
+/// How `run` prints its results.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Json,
+    Csv,
+}
+
+/// The result of solving one part of one day, flattened for machine-readable output.
+pub struct Row {
+    pub day: u32,
+    pub name: &'static str,
+    pub part: Part,
+    pub answer: Result<Answer, String>,
+    /// `None` if the input could not be parsed, or the answer was cached.
+    pub parse: Option<Duration>,
+    /// `None` if the part was not attempted.
+    pub solve: Option<Duration>,
+    pub source: Source,
+}
+
+const COLUMNS: [&str; 8] = [
+    "day", "name", "part", "answer", "error", "parse_ns", "solve_ns", "source",
+];
+
+/// Every row as a JSON array of objects, with one object per line.
+pub fn json(rows: &[Row]) -> String {
+    let mut output = String::from("[");
+    for (i, row) in rows.iter().enumerate() {
+        output.push_str(if i == 0 { "\n  {" } else { ",\n  {" });
+        for (j, (name, value)) in COLUMNS.iter().zip(fields(row)).enumerate() {
+            if j > 0 {
+                output.push_str(", ");
+            }
+            let value = match value {
+                Field::Text(s) => json_string(&s),
+                Field::Number(n) => n.to_string(),
+                Field::Null => "null".to_string(),
+            };
+            write!(output, "\"{}\": {}", name, value).unwrap();
+        }
+        output.push('}');
+    }
+    output.push_str(if rows.is_empty() { "]\n" } else { "\n]\n" });
+    output
+}
+
+/// Every row as CSV, with a header. Missing values are empty.
+pub fn csv(rows: &[Row]) -> String {
+    let mut output = COLUMNS.join(",");
+    output.push('\n');
+    for row in rows {
+        let cells = fields(row)
+            .iter()
+            .map(|value| match value {
+                Field::Text(s) => csv_cell(s),
+                Field::Number(n) => n.to_string(),
+                Field::Null => String::new(),
+            })
+            .collect::<Vec<_>>();
+        output.push_str(&cells.join(","));
+        output.push('\n');
+    }
+    output
+}
+
+enum Field {
+    Text(String),
+    Number(u128),
+    Null,
+}
+
+/// The values of a row, in the order of [`COLUMNS`].
+fn fields(row: &Row) -> [Field; 8] {
+    let duration = |d: Option<Duration>| d.map_or(Field::Null, |d| Field::Number(d.as_nanos()));
+    let (answer, error) = match &row.answer {
+        Ok(answer) => (Field::Number((*answer).into()), Field::Null),
+        Err(e) => (Field::Null, Field::Text(e.clone())),
+    };
+    [
+        Field::Number(row.day.into()),
+        Field::Text(row.name.to_string()),
+        Field::Number(row.part.number().into()),
+        answer,
+        error,
+        duration(row.parse),
+        duration(row.solve),
+        Field::Text(row.source.to_string()),
+    ]
+}
+
+pub fn json_string(s: &str) -> String {
+    let mut output = String::with_capacity(s.len() + 2);
+    output.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(output, "\\u{:04x}", c as u32).unwrap(),
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+    output
+}
+
+/// Quotes a cell if it contains a separator, quote or line break.
+fn csv_cell(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows() -> Vec<Row> {
+        vec![
+            Row {
+                day: 6,
+                name: "Lanternfish",
+                part: Part::One,
+                answer: Ok(5934),
+                parse: Some(Duration::from_micros(2)),
+                solve: Some(Duration::from_nanos(1500)),
+                source: Source::Embedded,
+            },
+            Row {
+                day: 6,
+                name: "Lanternfish",
+                part: Part::Two,
+                answer: Err("day 6, line 1, column 5: \"x\", oops".to_string()),
+                parse: None,
+                solve: None,
+                source: Source::Stdin,
+            },
+        ]
+    }
+
+    #[test]
+    fn writes_json() {
+        assert_eq!(
+            r#"[
+  {"day": 6, "name": "Lanternfish", "part": 1, "answer": 5934, "error": null, "parse_ns": 2000, "solve_ns": 1500, "source": "embedded"},
+  {"day": 6, "name": "Lanternfish", "part": 2, "answer": null, "error": "day 6, line 1, column 5: \"x\", oops", "parse_ns": null, "solve_ns": null, "source": "stdin"}
+]
+"#,
+            json(&rows())
+        );
+        assert_eq!("[]\n", json(&[]));
+    }
+
+    #[test]
+    fn writes_csv() {
+        assert_eq!(
+            r#"day,name,part,answer,error,parse_ns,solve_ns,source
+6,Lanternfish,1,5934,,2000,1500,embedded
+6,Lanternfish,2,,"day 6, line 1, column 5: ""x"", oops",,,stdin
+"#,
+            csv(&rows())
+        );
+    }
+
+    #[test]
+    fn escapes_json_strings() {
+        assert_eq!(r#""a\\b\n\u0001""#, json_string("a\\b\n\u{1}"));
+    }
+}
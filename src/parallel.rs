@@ -0,0 +1,87 @@
+//! Running independent work on a number of threads, keeping the results in order.
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+/// One worker for each processor, or one if that can't be found.
+pub fn default_workers() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Applies `f` to every item on up to `workers` threads, returning the results
+/// in the same order as the items, however long each takes.
+pub fn map<T: Sync, R: Send>(items: &[T], workers: usize, f: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    let workers = workers.clamp(1, items.len().max(1));
+    if workers == 1 {
+        return items.iter().map(f).collect();
+    }
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(items.iter().map(|_| None).collect::<Vec<_>>());
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                // each worker takes the next item until there are none left.
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let item = match items.get(index) {
+                        Some(item) => item,
+                        None => break,
+                    };
+                    let result = f(item);
+                    results.lock().unwrap()[index] = Some(result);
+                }
+            });
+        }
+    });
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|r| r.expect("every item is mapped"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn keeps_results_in_order() {
+        let items = (0..50u64).collect::<Vec<_>>();
+        let slow_first = |&i: &u64| {
+            // the earliest items finish last.
+            thread::sleep(Duration::from_micros(50 - i));
+            i * i
+        };
+        let expected = items.iter().map(|i| i * i).collect::<Vec<_>>();
+        for &workers in &[1, 4, 100] {
+            assert_eq!(
+                expected,
+                map(&items, workers, slow_first),
+                "{} workers",
+                workers
+            );
+        }
+        assert_eq!(Vec::<u64>::new(), map(&[], 4, slow_first));
+    }
+
+    #[test]
+    fn uses_many_threads() {
+        let items = [(); 8];
+        let threads = map(&items, 4, |_| {
+            thread::sleep(Duration::from_millis(10));
+            thread::current().id()
+        });
+        let mut distinct = threads.clone();
+        distinct.sort_by_key(|id| format!("{:?}", id));
+        distinct.dedup();
+        assert!(distinct.len() > 1);
+        assert!(!threads.contains(&thread::current().id()));
+    }
+}
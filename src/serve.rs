@@ -0,0 +1,418 @@
+//! A small HTTP server, so that the solvers can be called without linking Rust.
+//!
+//! `POST /day/{n}/part/{p}` with the puzzle input as the body answers with
+//! JSON, such as `{"day": 1, "part": 2, "answer": 5, "parse_ns": 1200, "solve_ns": 300}`,
+//! or `{"error": "...", "kind": "parse", "line": 2, "column": 4}` if the input
+//! can't be solved.
+
+use std::{
+    fmt::Write as _,
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        mpsc::{self, SyncSender},
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use rust_aoc_2021::{parallel, solution, AocError, Part, Solution};
+
+use crate::output::json_string;
+
+/// The largest input which will be read, in bytes.
+const MAX_BODY: usize = 64 * 1024 * 1024;
+/// The longest request line or header which will be read, in bytes.
+const MAX_LINE: usize = 8 * 1024;
+const MAX_HEADERS: usize = 100;
+/// The fewest connections answered at once, so that a few slow clients
+/// can't hold up the rest on a machine with few processors.
+const MIN_WORKERS: usize = 4;
+
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub body: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    /// Always JSON.
+    pub body: String,
+}
+impl Response {
+    fn error(status: u16, message: &str) -> Self {
+        Self {
+            status,
+            body: format!("{{\"error\": {}}}\n", json_string(message)),
+        }
+    }
+}
+
+/// Answers requests until the listener fails, on one thread for each
+/// processor. Connections wait to be accepted while every thread is busy.
+pub fn serve(listener: TcpListener) -> Result<(), String> {
+    let workers = parallel::default_workers().max(MIN_WORKERS);
+    let (sender, receiver) = mpsc::sync_channel(workers);
+    let receiver = Mutex::new(receiver);
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                // a statement of its own, so the lock isn't held while responding.
+                let next = receiver.lock().unwrap().recv();
+                match next {
+                    Ok(stream) => {
+                        if let Err(e) = respond(stream) {
+                            eprintln!("error: {}", e);
+                        }
+                    }
+                    Err(_) => break,
+                }
+            });
+        }
+        accept(&listener, sender)
+    })
+}
+
+/// Passes each connection to the workers, which stop once this returns.
+fn accept(listener: &TcpListener, workers: SyncSender<TcpStream>) -> Result<(), String> {
+    for stream in listener.incoming() {
+        let stream = stream.map_err(|e| format!("couldn't accept a connection: {}", e))?;
+        workers
+            .send(stream)
+            .expect("the workers outlive the listener");
+    }
+    Ok(())
+}
+
+/// Reads one request from the connection, and writes its response.
+fn respond(mut stream: TcpStream) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(30)))?;
+    let response = match read_request(&mut BufReader::new(&stream)) {
+        Ok(request) => handle(&request),
+        Err(response) => response,
+    };
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\n",
+        response.status,
+        reason(response.status)
+    );
+    write!(
+        head,
+        "Content-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.body.len()
+    )
+    .unwrap();
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(response.body.as_bytes())?;
+    stream.flush()
+}
+
+/// Reads the request line, headers and body. The body must have a `Content-Length`.
+pub fn read_request(reader: &mut impl BufRead) -> Result<Request, Response> {
+    let bad = |message: &str| Response::error(400, message);
+    let mut line = String::new();
+    // `None` if the line is longer than MAX_LINE.
+    let mut read_line = |line: &mut String| {
+        line.clear();
+        // room for the line ending, too.
+        let limit = MAX_LINE + 2;
+        match reader.by_ref().take(limit as u64).read_line(line) {
+            Ok(0) | Err(_) => Err(bad("the request ended early")),
+            Ok(n) if n == limit && !line.ends_with('\n') => Ok(None),
+            Ok(_) => Ok(Some(line.trim_end().to_string())),
+        }
+    };
+    let request_line = read_line(&mut line)?.ok_or_else(|| bad("the request line is too long"))?;
+    let (method, path) = match request_line.split(' ').collect::<Vec<_>>()[..] {
+        [method, target, version] if version.starts_with("HTTP/1.") => {
+            let path = target.split('?').next().unwrap_or(target);
+            (method.to_string(), path.to_string())
+        }
+        _ => {
+            return Err(bad(
+                "expected a request line such as 'POST /day/1/part/1 HTTP/1.1'",
+            ))
+        }
+    };
+    let too_large = |message: &str| Response::error(431, message);
+    let mut length = None;
+    for count in 0.. {
+        let header = read_line(&mut line)?.ok_or_else(|| too_large("a header is too long"))?;
+        if header.is_empty() {
+            break;
+        }
+        if count == MAX_HEADERS {
+            return Err(too_large("there are too many headers"));
+        }
+        let (name, value) = header
+            .split_once(':')
+            .ok_or_else(|| bad("expected a header such as 'Content-Length: 12'"))?;
+        if name.eq_ignore_ascii_case("content-length") {
+            length = Some(
+                value
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|_| bad("Content-Length must be a number"))?,
+            );
+        } else if name.eq_ignore_ascii_case("transfer-encoding") {
+            return Err(Response::error(411, "the body must have a Content-Length"));
+        }
+    }
+    let length = match (length, method.as_str()) {
+        (Some(length), _) => length,
+        (None, "POST") => return Err(Response::error(411, "the body must have a Content-Length")),
+        (None, _) => 0,
+    };
+    if length > MAX_BODY {
+        return Err(Response::error(413, "the input is too large"));
+    }
+    let mut body = vec![0; length];
+    reader
+        .read_exact(&mut body)
+        .map_err(|_| bad("the body was shorter than its Content-Length"))?;
+    Ok(Request { method, path, body })
+}
+
+/// Solves the part named by the path from the request body.
+pub fn handle(request: &Request) -> Response {
+    let segments = request
+        .path
+        .trim_matches('/')
+        .split('/')
+        .collect::<Vec<_>>();
+    let (day, part) = match segments[..] {
+        ["day", day, "part", part] => (day, part),
+        _ => return Response::error(404, "expected a path such as /day/1/part/2"),
+    };
+    let s = match day.parse().ok().and_then(solution::find) {
+        Some(s) => s,
+        None => return Response::error(404, &format!("day {} has no solution", day)),
+    };
+    let part = match part {
+        "1" => Part::One,
+        "2" => Part::Two,
+        _ => return Response::error(404, &format!("part must be 1 or 2, but was '{}'", part)),
+    };
+    if request.method != "POST" {
+        return Response::error(405, "the input must be sent with POST");
+    }
+    let input = match std::str::from_utf8(&request.body) {
+        Ok(input) => input,
+        Err(_) => return Response::error(400, "the input must be UTF-8 text"),
+    };
+
+    contain(|| solve(s, part, input))
+}
+
+/// The response of `respond`, or 500 if it panics.
+fn contain(respond: impl FnOnce() -> Response) -> Response {
+    panic::catch_unwind(AssertUnwindSafe(respond))
+        .unwrap_or_else(|_| Response::error(500, "the solver failed unexpectedly"))
+}
+
+fn solve(s: &dyn Solution, part: Part, input: &str) -> Response {
+    let solved = s.timed(input, &[part]).and_then(|timings| {
+        let p = &timings.parts[0];
+        p.answer
+            .clone()
+            .map(|answer| (answer, timings.parse, p.elapsed))
+    });
+    match solved {
+        Ok((answer, parse, solve)) => Response {
+            status: 200,
+            body: format!(
+                "{{\"day\": {}, \"part\": {}, \"answer\": {}, \"parse_ns\": {}, \"solve_ns\": {}}}\n",
+                s.day(),
+                part.number(),
+                answer,
+                parse.as_nanos(),
+                solve.as_nanos()
+            ),
+        },
+        Err(e) => Response {
+            status: 422,
+            body: error_json(&e),
+        },
+    }
+}
+
+/// The error's message and kind, and where in the input it was found.
+fn error_json(e: &AocError) -> String {
+    let kind = match e {
+        AocError::Parse { .. } => "parse",
+        AocError::InvalidInput { .. } => "invalid_input",
+        AocError::Unsolvable { .. } => "unsolvable",
+        AocError::Io { .. } => "io",
+    };
+    let mut output = format!(
+        "{{\"error\": {}, \"kind\": \"{}\"",
+        json_string(&e.to_string()),
+        kind
+    );
+    if let AocError::Parse { line, column, .. } = e {
+        write!(output, ", \"line\": {}, \"column\": {}", line, column).unwrap();
+    }
+    output.push_str("}\n");
+    output
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        411 => "Length Required",
+        413 => "Payload Too Large",
+        422 => "Unprocessable Entity",
+        431 => "Request Header Fields Too Large",
+        500 => "Internal Server Error",
+        _ => "Unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn post(path: &str, body: &str) -> Response {
+        handle(&Request {
+            method: "POST".to_string(),
+            path: path.to_string(),
+            body: body.as_bytes().to_vec(),
+        })
+    }
+
+    #[test]
+    fn answers_parts() {
+        let response = post("/day/1/part/2", rust_aoc_2021::day01::EXAMPLE);
+        assert_eq!(200, response.status);
+        assert!(
+            response
+                .body
+                .starts_with("{\"day\": 1, \"part\": 2, \"answer\": 5, \"parse_ns\": "),
+            "{}",
+            response.body
+        );
+    }
+
+    #[test]
+    fn reports_errors() {
+        assert_eq!(
+            Response {
+                status: 422,
+                body: "{\"error\": \"day 1, line 2, column 1: 'x' is not a valid number: invalid digit found in string\", \"kind\": \"parse\", \"line\": 2, \"column\": 1}\n".to_string(),
+            },
+            post("/day/1/part/1", "1\nx\n")
+        );
+        assert_eq!(422, post("/day/7/part/1", "").status);
+        assert_eq!(404, post("/day/26/part/1", "").status);
+        assert_eq!(404, post("/day/1/part/3", "").status);
+        assert_eq!(404, post("/days", "").status);
+        let get = Request {
+            method: "GET".to_string(),
+            path: "/day/1/part/1".to_string(),
+            body: Vec::new(),
+        };
+        assert_eq!(405, handle(&get).status);
+    }
+
+    #[test]
+    fn reads_requests() {
+        let text = "POST /day/6/part/1?verbose HTTP/1.1\r\nHost: x\r\ncontent-length: 10\r\n\r\n3,4,3,1,2\nextra";
+        let request = read_request(&mut text.as_bytes())
+            .map_err(|r| r.body)
+            .unwrap();
+        assert_eq!(
+            ("POST", "/day/6/part/1"),
+            (request.method.as_str(), request.path.as_str())
+        );
+        assert_eq!(b"3,4,3,1,2\n".to_vec(), request.body);
+
+        let status = |text: &str| read_request(&mut text.as_bytes()).err().map(|r| r.status);
+        assert_eq!(Some(411), status("POST /day/1/part/1 HTTP/1.1\r\n\r\n"));
+        assert_eq!(
+            Some(400),
+            status("POST /day/1/part/1 HTTP/1.1\r\nContent-Length: 5\r\n\r\n1")
+        );
+        assert_eq!(Some(400), status("hello\r\n\r\n"));
+        assert_eq!(
+            Some(413),
+            status("POST / HTTP/1.1\r\nContent-Length: 999999999999\r\n\r\n")
+        );
+
+        let long = "x".repeat(MAX_LINE + 1);
+        assert_eq!(
+            Some(400),
+            status(&format!("POST /{} HTTP/1.1\r\n\r\n", long))
+        );
+        assert_eq!(
+            Some(431),
+            status(&format!("POST / HTTP/1.1\r\nHost: {}\r\n\r\n", long))
+        );
+        let many = "Host: x\r\n".repeat(MAX_HEADERS + 1);
+        assert_eq!(
+            Some(431),
+            status(&format!("POST / HTTP/1.1\r\n{}\r\n", many))
+        );
+        let enough = "Host: x\r\n".repeat(MAX_HEADERS);
+        assert_eq!(None, status(&format!("GET / HTTP/1.1\r\n{}\r\n", enough)));
+    }
+
+    #[test]
+    fn contains_panics() {
+        let response = contain(|| panic!("solver bug"));
+        assert_eq!(500, response.status);
+        assert_eq!(
+            "{\"error\": \"the solver failed unexpectedly\"}\n",
+            response.body
+        );
+    }
+
+    #[test]
+    fn serves_over_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        std::thread::spawn(move || serve(listener));
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        let input = rust_aoc_2021::day06::EXAMPLE;
+        write!(
+            stream,
+            "POST /day/6/part/1 HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+            input.len(),
+            input
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+        assert!(response.contains("\"answer\": 5934,"), "{}", response);
+
+        // more connections than workers are all answered, in time.
+        let clients = (0..3 * MIN_WORKERS)
+            .map(|_| {
+                std::thread::spawn(move || {
+                    let mut stream = TcpStream::connect(address).unwrap();
+                    write!(
+                        stream,
+                        "POST /day/1/part/1 HTTP/1.1\r\nContent-Length: 2\r\n\r\n1\n"
+                    )
+                    .unwrap();
+                    let mut response = String::new();
+                    stream.read_to_string(&mut response).unwrap();
+                    response
+                })
+            })
+            .collect::<Vec<_>>();
+        for client in clients {
+            let response = client.join().unwrap();
+            assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use std::time::{Duration, Instant};
+
+use crate::{day01, day02, day03, day04, day05, day06, day07, day08};
+use crate::{
+    error::AocError,
+    input::normalize,
+    memory::{self, Usage},
+    trace,
+};
+
+/// The answer to a single part of a puzzle.
+pub type Answer = u64;
+
+/// One of the two questions asked of each puzzle input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Part {
+    One,
+    Two,
+}
+impl Part {
+    pub const BOTH: [Part; 2] = [Part::One, Part::Two];
+
+    pub fn number(self) -> u32 {
+        match self {
+            Part::One => 1,
+            Part::Two => 2,
+        }
+    }
+}
+
+/// A day's puzzle, implemented as a parse of the input into some model,
+/// followed by the two parts which answer questions about that model.
+pub trait Puzzle {
+    /// The day of the advent calendar, starting at 1.
+    const DAY: u32;
+    /// The puzzle title.
+    const NAME: &'static str;
+    /// The puzzle input embedded in the binary.
+    const INPUT: &'static str;
+    /// Increased whenever the solver changes, so that answers
+    /// [cached](crate::cache) from the old solver are not reused.
+    const VERSION: u32 = 1;
+
+    type Parsed<'a>;
+    fn parse(input: &str) -> Result<Self::Parsed<'_>, AocError>;
+    fn part1(parsed: &Self::Parsed<'_>) -> Result<Answer, AocError>;
+    fn part2(parsed: &Self::Parsed<'_>) -> Result<Answer, AocError>;
+
+    /// Answers a part in the slowest, most obvious way, to check the solvers
+    /// against; see [`crosscheck`](crate::crosscheck).
+    fn reference(parsed: &Self::Parsed<'_>, part: Part) -> Result<Answer, AocError>;
+}
+
+/// A single day's puzzle, solvable from its text input.
+///
+/// This is implemented for every [`Puzzle`], and exists so that days with
+/// different parsed models can live together in [`DAYS`]. Inputs are
+/// [normalized](normalize) before they are parsed. Solutions are `Sync`, so
+/// that days can be solved in parallel.
+pub trait Solution: Sync {
+    fn day(&self) -> u32;
+    fn name(&self) -> &'static str;
+    fn input(&self) -> &'static str;
+    fn version(&self) -> u32;
+    fn part1(&self, input: &str) -> Result<Answer, AocError>;
+    fn part2(&self, input: &str) -> Result<Answer, AocError>;
+
+    /// Parses the input once, and then solves each of `parts`,
+    /// timing every step separately. The memory each step allocates is
+    /// [measured](memory::measure) too, if it is being counted.
+    fn timed(&self, input: &str, parts: &[Part]) -> Result<Timings, AocError>;
+
+    /// Solves a part with the day's reference implementation.
+    fn reference(&self, part: Part, input: &str) -> Result<Answer, AocError>;
+
+    fn solve(&self, part: Part, input: &str) -> Result<Answer, AocError> {
+        match part {
+            Part::One => self.part1(input),
+            Part::Two => self.part2(input),
+        }
+    }
+}
+impl<T: Puzzle + Sync> Solution for T {
+    fn day(&self) -> u32 {
+        T::DAY
+    }
+    fn name(&self) -> &'static str {
+        T::NAME
+    }
+    fn input(&self) -> &'static str {
+        T::INPUT
+    }
+    fn version(&self) -> u32 {
+        T::VERSION
+    }
+    fn part1(&self, input: &str) -> Result<Answer, AocError> {
+        T::part1(&T::parse(&normalize(input))?)
+    }
+    fn part2(&self, input: &str) -> Result<Answer, AocError> {
+        T::part2(&T::parse(&normalize(input))?)
+    }
+    fn reference(&self, part: Part, input: &str) -> Result<Answer, AocError> {
+        T::reference(&T::parse(&normalize(input))?, part)
+    }
+    fn timed(&self, input: &str, parts: &[Part]) -> Result<Timings, AocError> {
+        let start = Instant::now();
+        let input = normalize(input);
+        let (parsed, parse_memory) = memory::measure(|| T::parse(&input));
+        let parsed = parsed?;
+        let parse = start.elapsed();
+        let parts = parts
+            .iter()
+            .map(|&part| {
+                trace::emit("solve", || vec![("part", part.number().to_string())]);
+                let start = Instant::now();
+                let (answer, memory) = memory::measure(|| match part {
+                    Part::One => T::part1(&parsed),
+                    Part::Two => T::part2(&parsed),
+                });
+                PartTiming {
+                    part,
+                    answer,
+                    elapsed: start.elapsed(),
+                    memory,
+                }
+            })
+            .collect();
+        Ok(Timings {
+            parse,
+            parse_memory,
+            parts,
+        })
+    }
+}
+
+pub struct Timings {
+    pub parse: Duration,
+    /// `None` unless memory is being [counted](memory::counting).
+    pub parse_memory: Option<Usage>,
+    pub parts: Vec<PartTiming>,
+}
+impl Timings {
+    pub fn total(&self) -> Duration {
+        self.parse + self.parts.iter().map(|p| p.elapsed).sum::<Duration>()
+    }
+}
+
+pub struct PartTiming {
+    pub part: Part,
+    pub answer: Result<Answer, AocError>,
+    pub elapsed: Duration,
+    pub memory: Option<Usage>,
+}
+
+/// Every solved day, in calendar order. Adding a day means adding it here.
+pub const DAYS: &[&dyn Solution] = &[
+    &day01::Day01,
+    &day02::Day02,
+    &day03::Day03,
+    &day04::Day04,
+    &day05::Day05,
+    &day06::Day06,
+    &day07::Day07,
+    &day08::Day08,
+];
+
+/// Gets the solution for the given day, if it has been registered.
+pub fn find(day: u32) -> Option<&'static dyn Solution> {
+    DAYS.iter().copied().find(|s| s.day() == day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registry_is_in_calendar_order() {
+        let days = DAYS.iter().map(|s| s.day()).collect::<Vec<_>>();
+        assert_eq!((1..=DAYS.len() as u32).collect::<Vec<_>>(), days);
+    }
+
+    #[test]
+    fn finds_registered_day() {
+        assert_eq!(Some("Hydrothermal Venture"), find(5).map(|s| s.name()));
+        assert!(find(25).is_none());
+    }
+
+    /// As if each example had been saved by a Windows editor.
+    fn windows_edited(input: &str) -> String {
+        let mut output = String::from("\u{feff}");
+        for line in input.lines() {
+            output.push_str(line);
+            output.push_str("  \r\n");
+        }
+        output.push_str("\r\n \r\n");
+        output
+    }
+
+    #[test]
+    fn solves_windows_edited_inputs() {
+        for e in crate::examples::EXAMPLES {
+            let s = find(e.day).unwrap();
+            let input = windows_edited(e.input);
+            for &part in &Part::BOTH {
+                assert_eq!(
+                    s.solve(part, e.input),
+                    s.solve(part, &input),
+                    "day {} {} part {}",
+                    e.day,
+                    e.name,
+                    part.number()
+                );
+            }
+            assert!(s.timed(&input, &Part::BOTH).is_ok());
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! Files of numbers keyed by day and input hash, stored as a small subset of TOML:
+//!
+//! ```toml
+//! # comment
+//! [day05.0123456789abcdef]
+//! part1 = 4655
+//! part2 = 20500
+//! ```
+
+use std::{fmt::Write, path::Path};
+
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, take_while1},
+    character::complete::{char, line_ending, not_line_ending, space0, u32, u64},
+    combinator::{all_consuming, eof, map, map_res, opt, value},
+    multi::many0,
+    sequence::{delimited, preceded, separated_pair, terminated, tuple},
+    IResult,
+};
+
+use crate::error::locate;
+
+/// The day and input hash of a section, followed by its `key = value` entries.
+pub type Section<'a> = ((u32, u64), Vec<(&'a str, u64)>);
+
+pub fn parse(s: &str) -> Result<Vec<Section<'_>>, String> {
+    let (_, sections) = all_consuming(preceded(ignored, many0(section)))(s).map_err(
+        |e: nom::Err<nom::error::Error<&str>>| {
+            let rest = match &e {
+                nom::Err::Error(e) | nom::Err::Failure(e) => e.input,
+                nom::Err::Incomplete(_) => &s[s.len()..],
+            };
+            format!("unexpected content at line {}", locate(s, rest).0)
+        },
+    )?;
+    Ok(sections)
+}
+
+/// Writes the comment lines, then each section.
+pub fn write<'a>(
+    comments: &[&str],
+    sections: impl IntoIterator<Item = ((u32, u64), Vec<(&'a str, u64)>)>,
+) -> String {
+    let mut output = String::new();
+    for c in comments {
+        writeln!(output, "# {}", c).unwrap();
+    }
+    for ((day, hash), entries) in sections {
+        writeln!(output).unwrap();
+        writeln!(output, "[day{:02}.{:016x}]", day, hash).unwrap();
+        for (key, value) in entries {
+            writeln!(output, "{} = {}", key, value).unwrap();
+        }
+    }
+    output
+}
+
+/// Reads a file. A missing file is empty.
+pub fn read(path: &Path) -> Result<String, String> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => Ok(text),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(format!("couldn't read {}: {}", path.display(), e)),
+    }
+}
+
+pub fn save(path: &Path, text: &str) -> Result<(), String> {
+    std::fs::write(path, text).map_err(|e| format!("couldn't write {}: {}", path.display(), e))
+}
+
+/// `[dayNN.HASH]` followed by `key = VALUE` lines.
+fn section(input: &str) -> IResult<&str, Section<'_>> {
+    let hash = map_res(take_while1(|c: char| c.is_ascii_hexdigit()), |h| {
+        u64::from_str_radix(h, 16)
+    });
+    let header = delimited(
+        char('['),
+        separated_pair(preceded(tag("day"), u32), char('.'), hash),
+        char(']'),
+    );
+    let key = take_while1(|c: char| c.is_ascii_alphanumeric() || c == '_');
+    let entry = map(
+        tuple((key, space0, char('='), space0, u64)),
+        |(key, _, _, _, value)| (key, value),
+    );
+    tuple((
+        terminated(header, end_of_line),
+        many0(terminated(entry, end_of_line)),
+    ))(input)
+}
+
+/// The rest of a line, including any comment, and any blank or comment lines which follow.
+fn end_of_line(input: &str) -> IResult<&str, ()> {
+    let (input, _) = tuple((space0, opt(comment), alt((line_ending, eof))))(input)?;
+    ignored(input)
+}
+
+fn ignored(input: &str) -> IResult<&str, ()> {
+    value(
+        (),
+        many0(alt((
+            value((), tuple((space0, comment, opt(line_ending)))),
+            value((), tuple((space0, line_ending))),
+        ))),
+    )(input)
+}
+
+fn comment(input: &str) -> IResult<&str, &str> {
+    preceded(char('#'), not_line_ending)(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sections() {
+        assert_eq!(
+            Ok(vec![
+                ((5, 0xff), vec![("part1", 4655), ("total_ns", 12)]),
+                ((6, 0), vec![]),
+            ]),
+            parse("# comment\n\n[day05.00000000000000ff]  # trailing\npart1 = 4655\n\ntotal_ns=12\n[day06.0]")
+        );
+        assert_eq!(
+            Err("unexpected content at line 2".to_string()),
+            parse("[day05.ff]\npart 1 = 1\n")
+        );
+    }
+
+    #[test]
+    fn round_trips_sections() {
+        let sections = vec![((1, 0x1234), vec![("parse", 1), ("part2", u64::MAX)])];
+        let text = write(&["a comment"], sections.clone());
+        assert!(text.starts_with("# a comment\n"));
+        assert_eq!(Ok(sections), parse(&text));
+    }
+}
@@ -0,0 +1,166 @@
+//! Reading puzzle inputs a line at a time, for inputs too large to hold in memory.
+
+use std::{io::BufRead, ops::Range};
+
+use crate::error::AocError;
+
+/// One line of input, without its line ending or trailing whitespace.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Line {
+    /// The line number, counting from 1.
+    pub number: usize,
+    pub text: String,
+}
+impl Line {
+    /// Moves an error found by parsing this line in isolation to this line.
+    pub fn locate(&self, e: AocError) -> AocError {
+        e.offset_lines(self.number - 1)
+    }
+}
+
+/// The lines of a reader, normalized as by [`normalize`](crate::input::normalize):
+/// a byte order mark and trailing whitespace are removed, and trailing blank
+/// lines are skipped.
+pub struct Lines<R> {
+    day: u32,
+    reader: R,
+    /// The number of the last line read.
+    number: usize,
+    /// Blank lines which have been read past, but not yet returned.
+    blanks: Range<usize>,
+    /// The line which ended a run of blank lines.
+    held: Option<Line>,
+}
+
+pub fn lines<R: BufRead>(day: u32, reader: R) -> Lines<R> {
+    Lines {
+        day,
+        reader,
+        number: 0,
+        blanks: 0..0,
+        held: None,
+    }
+}
+
+impl<R: BufRead> Lines<R> {
+    fn read_line(&mut self) -> Result<Option<Line>, AocError> {
+        let mut text = String::new();
+        let read = self.reader.read_line(&mut text).map_err(|e| AocError::Io {
+            day: self.day,
+            message: e.to_string(),
+        })?;
+        if read == 0 {
+            return Ok(None);
+        }
+        self.number += 1;
+        if self.number == 1 && text.starts_with('\u{feff}') {
+            text.remove(0);
+        }
+        text.truncate(text.trim_end().len());
+        Ok(Some(Line {
+            number: self.number,
+            text,
+        }))
+    }
+}
+
+impl<R: BufRead> Iterator for Lines<R> {
+    type Item = Result<Line, AocError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(number) = self.blanks.next() {
+            return Some(Ok(Line {
+                number,
+                text: String::new(),
+            }));
+        }
+        if let Some(line) = self.held.take() {
+            return Some(Ok(line));
+        }
+        let line = match self.read_line() {
+            Ok(Some(line)) if line.text.is_empty() => line,
+            other => return other.transpose(),
+        };
+        // a blank line is only returned if a line with content follows it.
+        loop {
+            match self.read_line() {
+                Ok(Some(next)) if next.text.is_empty() => (),
+                Ok(Some(next)) => {
+                    self.blanks = line.number + 1..next.number;
+                    self.held = Some(next);
+                    return Some(Ok(line));
+                }
+                other => return other.transpose(),
+            }
+        }
+    }
+}
+
+/// Passes on values until the first error, which is kept in `error`.
+///
+/// This lets a fallible stream be consumed by a solver which takes plain values.
+pub fn until_error<'a, T: 'a>(
+    iter: impl Iterator<Item = Result<T, AocError>> + 'a,
+    error: &'a mut Option<AocError>,
+) -> impl Iterator<Item = T> + 'a {
+    iter.map_while(move |r| match r {
+        Ok(value) => Some(value),
+        Err(e) => {
+            *error = Some(e);
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(input: &str) -> Vec<(usize, String)> {
+        lines(1, input.as_bytes())
+            .map(|l| l.map(|l| (l.number, l.text)).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn reads_normalized_lines() {
+        assert_eq!(
+            vec![
+                (1, "a".to_string()),
+                (2, String::new()),
+                (3, String::new()),
+                (4, " b".to_string())
+            ],
+            read("\u{feff}a \r\n\r\n\n b\t\r\n\r\n \n")
+        );
+        assert_eq!(Vec::<(usize, String)>::new(), read(""));
+        assert_eq!(vec![(1, "x".to_string())], read("x"));
+    }
+
+    #[test]
+    fn matches_normalize() {
+        for e in crate::examples::EXAMPLES {
+            let streamed = read(e.input)
+                .into_iter()
+                .map(|(_, text)| text + "\n")
+                .collect::<String>();
+            assert_eq!(crate::input::normalize(e.input), streamed);
+        }
+    }
+
+    #[test]
+    fn reports_bad_text() {
+        let result = lines(3, &b"ok\n\xff\n"[..]).collect::<Vec<_>>();
+        assert!(result[0].is_ok());
+        assert!(matches!(result[1], Err(AocError::Io { day: 3, .. })));
+    }
+
+    #[test]
+    fn stops_at_first_error() {
+        let mut error = None;
+        let values = vec![Ok(1), Ok(2), Err(AocError::invalid(1, "bad")), Ok(3)];
+        let sum: u32 = until_error(values.into_iter(), &mut error).sum();
+        assert_eq!(3, sum);
+        assert_eq!(Some(AocError::invalid(1, "bad")), error);
+    }
+}
@@ -0,0 +1,155 @@
+use std::{fmt::Display, time::Duration};
+
+#[derive(Clone, Copy)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+enum Row {
+    Cells(Vec<String>),
+    Rule,
+}
+
+/// A plain-text table, with every column padded to its widest cell.
+pub struct Table {
+    headings: Vec<(String, Align)>,
+    rows: Vec<Row>,
+}
+impl Table {
+    pub fn new(headings: &[(&str, Align)]) -> Self {
+        Self {
+            headings: headings
+                .iter()
+                .map(|&(h, align)| (h.to_string(), align))
+                .collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Adds a row. Missing trailing cells are left blank.
+    pub fn row(&mut self, cells: Vec<String>) {
+        assert!(cells.len() <= self.headings.len(), "Too many cells in row");
+        self.rows.push(Row::Cells(cells));
+    }
+
+    /// Adds a horizontal separator.
+    pub fn rule(&mut self) {
+        self.rows.push(Row::Rule);
+    }
+
+    fn widths(&self) -> Vec<usize> {
+        let mut widths = self
+            .headings
+            .iter()
+            .map(|(h, _)| h.chars().count())
+            .collect::<Vec<_>>();
+        for row in &self.rows {
+            if let Row::Cells(cells) = row {
+                for (w, cell) in widths.iter_mut().zip(cells) {
+                    *w = (*w).max(cell.chars().count());
+                }
+            }
+        }
+        widths
+    }
+}
+impl Display for Table {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let widths = self.widths();
+        let aligns = self.headings.iter().map(|(_, a)| *a).collect::<Vec<_>>();
+        let write_cells = |f: &mut std::fmt::Formatter<'_>, cells: &[String]| {
+            let mut line = String::new();
+            for (i, (&width, &align)) in widths.iter().zip(&aligns).enumerate() {
+                if i > 0 {
+                    line.push_str("  ");
+                }
+                let cell = cells.get(i).map_or("", String::as_str);
+                match align {
+                    Align::Left => line.push_str(&format!("{:<1$}", cell, width)),
+                    Align::Right => line.push_str(&format!("{:>1$}", cell, width)),
+                }
+            }
+            writeln!(f, "{}", line.trim_end())
+        };
+        let rule = "-".repeat(widths.iter().sum::<usize>() + 2 * (widths.len() - 1));
+
+        let headings = self
+            .headings
+            .iter()
+            .map(|(h, _)| h.clone())
+            .collect::<Vec<_>>();
+        write_cells(f, &headings)?;
+        writeln!(f, "{}", rule)?;
+        for row in &self.rows {
+            match row {
+                Row::Cells(cells) => write_cells(f, cells)?,
+                Row::Rule => writeln!(f, "{}", rule)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Formats a number of bytes with a binary unit suited to its size.
+pub fn format_bytes(bytes: u64) -> String {
+    const KIB: u64 = 1 << 10;
+    const MIB: u64 = 1 << 20;
+    if bytes < KIB {
+        format!("{}B", bytes)
+    } else if bytes < MIB {
+        format!("{:.1}KiB", bytes as f64 / KIB as f64)
+    } else {
+        format!("{:.1}MiB", bytes as f64 / MIB as f64)
+    }
+}
+
+/// Formats a duration with a unit suited to its size.
+pub fn format_duration(d: Duration) -> String {
+    let nanos = d.as_nanos();
+    if nanos < 1_000_000 {
+        format!("{:.1}µs", nanos as f64 / 1e3)
+    } else if nanos < 1_000_000_000 {
+        format!("{:.2}ms", nanos as f64 / 1e6)
+    } else {
+        format!("{:.3}s", d.as_secs_f64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aligns_columns() {
+        let mut t = Table::new(&[("Name", Align::Left), ("Count", Align::Right)]);
+        t.row(vec!["a".to_string(), "1".to_string()]);
+        t.row(vec!["longer".to_string(), "100000".to_string()]);
+        t.rule();
+        t.row(vec!["total".to_string()]);
+        assert_eq!(
+            "Name     Count
+--------------
+a            1
+longer  100000
+--------------
+total
+",
+            t.to_string()
+        );
+    }
+
+    #[test]
+    fn formats_durations() {
+        assert_eq!("12.3µs", format_duration(Duration::from_nanos(12_345)));
+        assert_eq!("4.57ms", format_duration(Duration::from_micros(4_567)));
+        assert_eq!("2.500s", format_duration(Duration::from_millis(2_500)));
+    }
+
+    #[test]
+    fn formats_bytes() {
+        assert_eq!("512B", format_bytes(512));
+        assert_eq!("1.5KiB", format_bytes(1536));
+        assert_eq!("3.0MiB", format_bytes(3 << 20));
+    }
+}
@@ -0,0 +1,144 @@
+//! Intermediate state from the solvers, for working out why an answer is wrong.
+//!
+//! Solvers describe each interesting step with [`emit`], which does nothing
+//! unless a sink has been installed on the current thread with [`with`] or
+//! [`capture`].
+
+use std::{cell::RefCell, fmt::Display, rc::Rc};
+
+/// One step of a solver, as a name and some named values.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Event {
+    pub name: &'static str,
+    pub fields: Vec<(&'static str, String)>,
+}
+
+/// Written as `name key=value key="quoted value"`.
+impl Display for Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name)?;
+        for (key, value) in &self.fields {
+            if value.is_empty() || value.contains([' ', '"', '=']) {
+                write!(f, " {}={:?}", key, value)?;
+            } else {
+                write!(f, " {}={}", key, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+type Sink = Box<dyn FnMut(Event)>;
+
+thread_local! {
+    static SINK: RefCell<Option<Sink>> = RefCell::new(None);
+}
+
+/// Whether events are being recorded, for solvers which must do extra work to describe a step.
+pub fn enabled() -> bool {
+    SINK.with(|s| s.borrow().is_some())
+}
+
+/// Records an event, if a sink is installed. The fields are only built if they will be used.
+pub fn emit(name: &'static str, fields: impl FnOnce() -> Vec<(&'static str, String)>) {
+    SINK.with(|s| {
+        if let Some(sink) = s.borrow_mut().as_mut() {
+            sink(Event {
+                name,
+                fields: fields(),
+            })
+        }
+    })
+}
+
+/// Runs `f`, passing every event it emits to `sink`.
+pub fn with<T>(sink: impl FnMut(Event) + 'static, f: impl FnOnce() -> T) -> T {
+    /// Puts back the previous sink, even if `f` panics.
+    struct Restore(Option<Sink>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            SINK.with(|s| *s.borrow_mut() = previous);
+        }
+    }
+    let _restore = Restore(SINK.with(|s| s.borrow_mut().replace(Box::new(sink))));
+    f()
+}
+
+/// Runs `f`, returning its result along with every event it emitted.
+pub fn capture<T>(f: impl FnOnce() -> T) -> (T, Vec<Event>) {
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let sink = Rc::clone(&events);
+    let output = with(move |e| sink.borrow_mut().push(e), f);
+    let events = events.take();
+    (output, events)
+}
+
+/// The events with a given name, as maps from field name to value, for tests.
+#[cfg(test)]
+pub fn named<'a>(
+    events: &'a [Event],
+    name: &'a str,
+) -> impl Iterator<Item = std::collections::HashMap<&'static str, &'a str>> + 'a {
+    events.iter().filter(move |e| e.name == name).map(|e| {
+        e.fields
+            .iter()
+            .map(|(key, value)| (*key, value.as_str()))
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(name: &'static str, fields: &[(&'static str, &str)]) -> Event {
+        Event {
+            name,
+            fields: fields.iter().map(|&(k, v)| (k, v.to_string())).collect(),
+        }
+    }
+
+    #[test]
+    fn formats_events() {
+        assert_eq!(
+            r#"position instruction="forward 5" x=5 note="""#,
+            event(
+                "position",
+                &[("instruction", "forward 5"), ("x", "5"), ("note", "")]
+            )
+            .to_string()
+        );
+        assert_eq!("done", event("done", &[]).to_string());
+    }
+
+    #[test]
+    fn captures_only_when_asked() {
+        let mut built = false;
+        emit("ignored", || {
+            built = true;
+            vec![]
+        });
+        assert!(!built);
+        assert!(!enabled());
+
+        let (answer, events) = capture(|| {
+            assert!(enabled());
+            emit("step", || vec![("n", 1.to_string())]);
+            42
+        });
+        assert_eq!(42, answer);
+        assert_eq!(vec![event("step", &[("n", "1")])], events);
+        assert!(!enabled());
+    }
+
+    #[test]
+    fn restores_outer_sink() {
+        let (_, outer) = capture(|| {
+            let (_, inner) = capture(|| emit("inner", Vec::new));
+            assert_eq!(1, inner.len());
+            emit("outer", Vec::new);
+        });
+        assert_eq!(vec![event("outer", &[])], outer);
+    }
+}
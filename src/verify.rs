@@ -0,0 +1,232 @@
+use std::{collections::BTreeMap, fmt::Display, path::Path};
+
+use crate::{
+    error::AocError,
+    input::Provider,
+    parallel,
+    solution::{Answer, Part, Solution},
+    store,
+};
+
+/// The file, relative to the working directory, where answers are recorded by default.
+pub const DEFAULT_PATH: &str = "answers.toml";
+
+/// Answers which are known to be correct, keyed by day and input hash.
+///
+/// Stored as a small subset of TOML:
+///
+/// ```toml
+/// [day05.0123456789abcdef]
+/// part1 = 4655
+/// part2 = 20500
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Answers(BTreeMap<(u32, u64), [Option<Answer>; 2]>);
+
+/// The outcome of checking an answer against the recorded answers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    /// The answer matches the one recorded for this input.
+    Pass,
+    /// The answer differs from the one recorded for this input.
+    Fail,
+    /// No answer is recorded for this input, so it has been changed
+    /// (or is new) since answers were recorded.
+    Changed,
+}
+impl Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Status::Pass => "PASS",
+            Status::Fail => "FAIL",
+            Status::Changed => "CHANGED",
+        })
+    }
+}
+
+impl Answers {
+    pub fn get(&self, day: u32, hash: u64, part: Part) -> Option<Answer> {
+        self.0.get(&(day, hash))?[part.number() as usize - 1]
+    }
+
+    /// Records an answer, replacing any previously recorded for the same input.
+    pub fn insert(&mut self, day: u32, hash: u64, part: Part, answer: Answer) {
+        self.0.entry((day, hash)).or_default()[part.number() as usize - 1] = Some(answer);
+    }
+
+    pub fn check(&self, day: u32, hash: u64, part: Part, answer: Answer) -> Status {
+        match self.get(day, hash, part) {
+            Some(expected) if expected == answer => Status::Pass,
+            Some(_) => Status::Fail,
+            None => Status::Changed,
+        }
+    }
+
+    /// Reads answers from a file. A missing file has no answers.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        store::read(path)?
+            .parse()
+            .map_err(|e| format!("couldn't parse {}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        store::save(path, &self.to_string())
+    }
+}
+
+impl std::str::FromStr for Answers {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut output = Self::default();
+        for ((day, hash), entries) in store::parse(s)? {
+            for (key, answer) in entries {
+                let part = match key {
+                    "part1" => Part::One,
+                    "part2" => Part::Two,
+                    other => return Err(format!("unknown key '{}' for day {}", other, day)),
+                };
+                output.insert(day, hash, part, answer);
+            }
+        }
+        Ok(output)
+    }
+}
+
+impl Display for Answers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sections = self.0.iter().map(|(&key, answers)| {
+            let entries = Part::BOTH
+                .iter()
+                .zip(answers)
+                .filter_map(|(part, answer)| {
+                    Some((["part1", "part2"][part.number() as usize - 1], (*answer)?))
+                })
+                .collect();
+            (key, entries)
+        });
+        f.write_str(&store::write(
+            &[
+                "Puzzle answers known to be correct, keyed by day and input hash.",
+                "Checked by `verify`, and added to by `verify --record`.",
+            ],
+            sections,
+        ))
+    }
+}
+
+/// The result of checking one part of one day.
+pub struct Check {
+    pub day: u32,
+    pub hash: u64,
+    pub part: Part,
+    /// The answer, or why there is none; an error always fails the check.
+    pub answer: Result<Answer, AocError>,
+    pub expected: Option<Answer>,
+    pub status: Status,
+}
+
+/// Solves both parts of every day in `days` on up to `workers` threads, and checks
+/// the answers against those recorded, in the order of `days`.
+pub fn verify(
+    days: &[&dyn Solution],
+    provider: &Provider,
+    answers: &Answers,
+    workers: usize,
+) -> Result<Vec<Check>, String> {
+    let checks = parallel::map(days, workers, |s| -> Result<_, String> {
+        let mut output = Vec::new();
+        let input = provider.load(*s)?;
+        let hash = input.hash();
+        let solved: Vec<_> = match s.timed(&input.text, &Part::BOTH) {
+            Ok(timings) => timings
+                .parts
+                .into_iter()
+                .map(|p| (p.part, p.answer))
+                .collect(),
+            Err(e) => Part::BOTH.iter().map(|&p| (p, Err(e.clone()))).collect(),
+        };
+        for (part, answer) in solved {
+            let status = match answer {
+                Ok(a) => answers.check(s.day(), hash, part, a),
+                Err(_) => Status::Fail,
+            };
+            output.push(Check {
+                day: s.day(),
+                hash,
+                part,
+                answer,
+                expected: answers.get(s.day(), hash, part),
+                status,
+            });
+        }
+        Ok(output)
+    });
+    let mut output = Vec::new();
+    for day in checks {
+        output.extend(day?);
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solution::DAYS;
+
+    const RECORDED: &str = include_str!("../answers.toml");
+
+    #[test]
+    fn parses_answers() {
+        let answers =
+            "# comment\n\n[day05.00000000000000ff]  # trailing\npart1 = 4655\n\npart2=20500\n"
+                .parse::<Answers>()
+                .unwrap();
+        assert_eq!(Some(4655), answers.get(5, 0xff, Part::One));
+        assert_eq!(Some(20500), answers.get(5, 0xff, Part::Two));
+        assert_eq!(None, answers.get(5, 0xfe, Part::One));
+    }
+
+    #[test]
+    fn rejects_malformed_answers() {
+        assert!("[day05.ff]\npart3 = 1\n".parse::<Answers>().is_err());
+        assert!("[day05]\npart1 = 1\n".parse::<Answers>().is_err());
+        assert!("[day05.ff]\npart1 = one\n".parse::<Answers>().is_err());
+    }
+
+    #[test]
+    fn round_trips_answers() {
+        let mut answers = Answers::default();
+        answers.insert(1, 0x1234, Part::One, 1688);
+        answers.insert(8, u64::MAX, Part::Two, 1097568);
+        assert_eq!(Ok(answers.clone()), answers.to_string().parse());
+    }
+
+    #[test]
+    fn checks_answers() {
+        let mut answers = Answers::default();
+        answers.insert(6, 1, Part::One, 5934);
+        assert_eq!(Status::Pass, answers.check(6, 1, Part::One, 5934));
+        assert_eq!(Status::Fail, answers.check(6, 1, Part::One, 5935));
+        assert_eq!(Status::Changed, answers.check(6, 1, Part::Two, 5934));
+        assert_eq!(Status::Changed, answers.check(6, 2, Part::One, 5934));
+    }
+
+    #[test]
+    fn embedded_inputs_match_recorded_answers() {
+        let answers = RECORDED.parse::<Answers>().unwrap();
+        let provider = Provider {
+            path: None,
+            dir: "no such directory".into(),
+        };
+        for check in verify(DAYS, &provider, &answers, 4).unwrap() {
+            assert_eq!(
+                Status::Pass,
+                check.status,
+                "day {} part {}",
+                check.day,
+                check.part.number()
+            );
+        }
+    }
+}